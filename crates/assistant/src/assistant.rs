@@ -13,7 +13,10 @@ mod streaming_diff;
 mod terminal_inline_assistant;
 
 pub use assistant_panel::{AssistantPanel, AssistantPanelEvent};
-use assistant_settings::{AnthropicModel, AssistantSettings, CloudModel, OllamaModel, OpenAiModel};
+use assistant_settings::{
+    AnthropicModel, AssistantProvider, AssistantSettings, CloudModel, OllamaModel, OllamaSettings,
+    OpenAiModel,
+};
 use assistant_slash_command::SlashCommandRegistry;
 use client::{proto, Client};
 use command_palette_hooks::CommandPaletteFilter;
@@ -25,7 +28,11 @@ use gpui::{actions, AppContext, Global, SharedString, UpdateGlobal};
 use indexed_docs::IndexedDocsRegistry;
 pub(crate) use inline_assistant::*;
 pub(crate) use model_selector::*;
-use semantic_index::{CloudEmbeddingProvider, SemanticIndex};
+use project::WorktreeId;
+use semantic_index::{
+    CloudEmbeddingProvider, EmbeddingProvider, OllamaEmbeddingModel, OllamaEmbeddingProvider,
+    SemanticIndex,
+};
 use serde::{Deserialize, Serialize};
 use settings::{Settings, SettingsStore};
 use slash_command::{
@@ -74,6 +81,8 @@ pub enum Role {
     User,
     Assistant,
     System,
+    /// The result of a tool the model called, fed back into the conversation.
+    Tool,
 }
 
 impl Role {
@@ -82,7 +91,7 @@ impl Role {
             Some(proto::LanguageModelRole::LanguageModelUser) => Role::User,
             Some(proto::LanguageModelRole::LanguageModelAssistant) => Role::Assistant,
             Some(proto::LanguageModelRole::LanguageModelSystem) => Role::System,
-            Some(proto::LanguageModelRole::LanguageModelTool) => Role::System,
+            Some(proto::LanguageModelRole::LanguageModelTool) => Role::Tool,
             None => Role::User,
         }
     }
@@ -92,6 +101,7 @@ impl Role {
             Role::User => proto::LanguageModelRole::LanguageModelUser,
             Role::Assistant => proto::LanguageModelRole::LanguageModelAssistant,
             Role::System => proto::LanguageModelRole::LanguageModelSystem,
+            Role::Tool => proto::LanguageModelRole::LanguageModelTool,
         }
     }
 
@@ -99,7 +109,7 @@ impl Role {
         match self {
             Role::User => Role::Assistant,
             Role::Assistant => Role::System,
-            Role::System => Role::User,
+            Role::System | Role::Tool => Role::User,
         }
     }
 }
@@ -110,6 +120,7 @@ impl Display for Role {
             Role::User => write!(f, "user"),
             Role::Assistant => write!(f, "assistant"),
             Role::System => write!(f, "system"),
+            Role::Tool => write!(f, "tool"),
         }
     }
 }
@@ -129,12 +140,32 @@ impl Default for LanguageModel {
 }
 
 impl LanguageModel {
+    /// Identifies the model in telemetry events by its [stable id](Self::to_stable_id).
     pub fn telemetry_id(&self) -> String {
+        self.to_stable_id()
+    }
+
+    /// Returns a string that identifies this model across restarts, such as
+    /// `ollama/llama3:latest`, for use as a key when persisting the model.
+    ///
+    /// Provider names never contain a `/`, so the first one separates the provider
+    /// from the model id, which may contain further slashes and colons.
+    pub fn to_stable_id(&self) -> String {
+        format!("{}/{}", self.provider_name(), self.id())
+    }
+
+    /// Splits an id returned by [`Self::to_stable_id`] into the provider name and
+    /// the model id.
+    pub fn parse_stable_id(stable_id: &str) -> Option<(&str, &str)> {
+        stable_id.split_once('/')
+    }
+
+    pub fn provider_name(&self) -> &'static str {
         match self {
-            LanguageModel::OpenAi(model) => format!("openai/{}", model.id()),
-            LanguageModel::Anthropic(model) => format!("anthropic/{}", model.id()),
-            LanguageModel::Cloud(model) => format!("zed.dev/{}", model.id()),
-            LanguageModel::Ollama(model) => format!("ollama/{}", model.id()),
+            LanguageModel::OpenAi(_) => "openai",
+            LanguageModel::Anthropic(_) => "anthropic",
+            LanguageModel::Cloud(_) => "zed.dev",
+            LanguageModel::Ollama(_) => "ollama",
         }
     }
 
@@ -147,6 +178,17 @@ impl LanguageModel {
         }
     }
 
+    /// Returns a one-line description of the model for pickers and tooltips, when
+    /// one is known.
+    pub fn description(&self) -> Option<String> {
+        match self {
+            LanguageModel::Ollama(model) => model.description().map(Into::into),
+            LanguageModel::OpenAi(_) | LanguageModel::Anthropic(_) | LanguageModel::Cloud(_) => {
+                None
+            }
+        }
+    }
+
     pub fn max_token_count(&self) -> usize {
         match self {
             LanguageModel::OpenAi(model) => model.max_token_count(),
@@ -164,12 +206,31 @@ impl LanguageModel {
             LanguageModel::Ollama(model) => model.id(),
         }
     }
+
+    /// Identifies the weights behind the model when the provider reports them, so
+    /// that models listed more than once under different names can be recognized.
+    pub fn fingerprint(&self) -> Option<&str> {
+        match self {
+            LanguageModel::Ollama(model) => model.metadata.digest.as_deref(),
+            LanguageModel::OpenAi(_) | LanguageModel::Anthropic(_) | LanguageModel::Cloud(_) => {
+                None
+            }
+        }
+    }
 }
 
 #[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
 pub struct LanguageModelRequestMessage {
     pub role: Role,
     pub content: String,
+    /// Images attached to the message. Ignored by providers that don't support
+    /// images.
+    #[serde(default)]
+    pub images: Vec<LanguageModelImage>,
+    /// The name of the tool whose result a [`Role::Tool`] message carries, so that
+    /// providers can match the result to its call.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_name: Option<String>,
 }
 
 impl LanguageModelRequestMessage {
@@ -183,12 +244,109 @@ impl LanguageModelRequestMessage {
     }
 }
 
+/// The contents of an image file, such as a PNG or JPEG, attached to a message.
+#[derive(Clone, Serialize, Deserialize, Debug, Eq, PartialEq)]
+pub struct LanguageModelImage(pub Vec<u8>);
+
+/// A tool the model may call, described by a JSON schema of its input.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LanguageModelTool {
+    pub name: String,
+    pub description: String,
+    pub input_schema: serde_json::Value,
+}
+
+/// A call the model made to one of the request's [`LanguageModelTool`]s.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct LanguageModelToolCall {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// A part of a completion streamed by
+/// [`LanguageModelCompletionProvider::complete_events`].
+#[derive(Clone, Debug, PartialEq)]
+pub enum LanguageModelCompletionEvent {
+    Text(String),
+    ToolCall(LanguageModelToolCall),
+}
+
+/// The shape a model's response must take, for features that parse it.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LanguageModelResponseFormat {
+    /// Any valid JSON.
+    Json,
+    /// JSON conforming to the given schema.
+    JsonSchema(serde_json::Value),
+}
+
+/// The kind of device to run a request on, for providers that run models locally.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Device {
+    Cpu,
+    Gpu,
+}
+
 #[derive(Debug, Default, Serialize, Deserialize)]
 pub struct LanguageModelRequest {
     pub model: LanguageModel,
     pub messages: Vec<LanguageModelRequestMessage>,
     pub stop: Vec<String>,
-    pub temperature: f32,
+    /// The temperature to sample the response with, or `None` for the model's
+    /// default.
+    pub temperature: Option<f32>,
+    /// The device to run the request on, overriding where the provider would place
+    /// the model. Ignored by providers that don't run models locally.
+    #[serde(default)]
+    pub prefer_device: Option<Device>,
+    /// The tools the model may call, reported by
+    /// [`LanguageModelCompletionProvider::complete_events`]. Providers that don't
+    /// support tools fail requests that offer any.
+    #[serde(default)]
+    pub tools: Vec<LanguageModelTool>,
+    /// Constrains the response to JSON. Ignored by providers that can't constrain
+    /// their output, so the response should still be validated.
+    #[serde(default)]
+    pub response_format: Option<LanguageModelResponseFormat>,
+    /// How to sample the response, overriding the options the model was configured
+    /// with.
+    #[serde(default)]
+    pub model_options: LanguageModelOptions,
+    /// The worktree whose project settings configure the provider that completes
+    /// the request, or `None` to use the global settings.
+    #[serde(skip)]
+    pub worktree_id: Option<WorktreeId>,
+}
+
+/// Options for sampling the response to a request, left to the model's defaults
+/// when unset. Providers ignore the options they don't support.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct LanguageModelOptions {
+    /// The most tokens to generate in the response.
+    pub max_tokens: Option<usize>,
+    /// Samples only from the most likely tokens whose probabilities add up to this.
+    pub top_p: Option<f32>,
+    /// Samples only from the given number of most likely tokens.
+    pub top_k: Option<usize>,
+    /// How strongly to penalize repeated tokens, where 1.0 doesn't penalize them.
+    pub repeat_penalty: Option<f32>,
+    /// The seed of the random number generator, for responses that can be
+    /// reproduced.
+    pub seed: Option<i64>,
+}
+
+/// A request for the text between `prefix` and `suffix`, such as the code to insert
+/// at the cursor, for models that can fill in the middle.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct FillInTheMiddleRequest {
+    pub model: LanguageModel,
+    pub prefix: String,
+    pub suffix: String,
+    pub stop: Vec<String>,
+    /// The temperature to sample the text with, or `None` for the model's default.
+    pub temperature: Option<f32>,
 }
 
 impl LanguageModelRequest {
@@ -197,7 +355,7 @@ impl LanguageModelRequest {
             model: self.model.id().to_string(),
             messages: self.messages.iter().map(|m| m.to_proto()).collect(),
             stop: self.stop.clone(),
-            temperature: self.temperature,
+            temperature: self.temperature.unwrap_or(1.0),
             tool_choice: None,
             tools: Vec::new(),
         }
@@ -317,24 +475,94 @@ impl Assistant {
     }
 }
 
-pub fn init(fs: Arc<dyn Fs>, client: Arc<Client>, cx: &mut AppContext) {
-    cx.set_global(Assistant::default());
-    AssistantSettings::register(cx);
+/// The Ollama embedding model and API URL configured for the assistant, if
+/// semantic indices should be built with Ollama.
+#[derive(Clone, PartialEq, Eq)]
+struct OllamaEmbeddingSettings {
+    model: String,
+    api_url: String,
+}
 
-    cx.spawn(|mut cx| {
-        let client = client.clone();
-        async move {
-            let embedding_provider = CloudEmbeddingProvider::new(client.clone());
-            let semantic_index = SemanticIndex::new(
-                paths::embeddings_dir().join("semantic-index-db.0.mdb"),
-                Arc::new(embedding_provider),
-                &mut cx,
-            )
-            .await?;
-            cx.update(|cx| cx.set_global(semantic_index))
+impl OllamaEmbeddingSettings {
+    fn get(cx: &AppContext) -> Option<Self> {
+        if let AssistantProvider::Ollama {
+            settings:
+                OllamaSettings {
+                    api_url,
+                    embedding_model: Some(embedding_model),
+                    ..
+                },
+            ..
+        } = &AssistantSettings::get_global(cx).provider
+        {
+            Some(Self {
+                model: embedding_model.clone(),
+                api_url: api_url.clone(),
+            })
+        } else {
+            None
         }
+    }
+}
+
+/// Returns the provider to build semantic indices with, along with the name of the
+/// database its embeddings are stored in. Ollama embeddings are kept apart from the
+/// others, since embeddings of different models can't be compared.
+fn embedding_provider(
+    client: &Arc<Client>,
+    settings: Option<&OllamaEmbeddingSettings>,
+) -> (Arc<dyn EmbeddingProvider>, String) {
+    if let Some(settings) = settings {
+        let db_name = format!(
+            "semantic-index-db.ollama-{}.0.mdb",
+            settings
+                .model
+                .replace(|c: char| !c.is_ascii_alphanumeric(), "-")
+        );
+        let provider = OllamaEmbeddingProvider::new(
+            client.http_client(),
+            OllamaEmbeddingModel::from_id(&settings.model),
+            settings.api_url.clone(),
+        );
+        return (Arc::new(provider), db_name);
+    }
+
+    (
+        Arc::new(CloudEmbeddingProvider::new(client.clone())),
+        "semantic-index-db.0.mdb".into(),
+    )
+}
+
+/// Builds the global semantic index for the given embedding settings. The index is
+/// only installed if the settings haven't changed again while it was being opened.
+fn init_semantic_index(
+    client: &Arc<Client>,
+    settings: Option<OllamaEmbeddingSettings>,
+    cx: &mut AppContext,
+) {
+    let (embedding_provider, db_name) = embedding_provider(client, settings.as_ref());
+    cx.spawn(|mut cx| async move {
+        let semantic_index = SemanticIndex::new(
+            paths::embeddings_dir().join(db_name),
+            embedding_provider,
+            &mut cx,
+        )
+        .await?;
+        cx.update(|cx| {
+            if OllamaEmbeddingSettings::get(cx) == settings {
+                cx.set_global(semantic_index);
+            }
+        })
     })
     .detach();
+}
+
+pub fn init(fs: Arc<dyn Fs>, client: Arc<Client>, cx: &mut AppContext) {
+    cx.set_global(Assistant::default());
+    AssistantSettings::register(cx);
+
+    let mut embedding_settings = OllamaEmbeddingSettings::get(cx);
+    init_semantic_index(&client, embedding_settings.clone(), cx);
 
     context_store::init(&client);
     prompt_library::init(cx);
@@ -354,11 +582,17 @@ pub fn init(fs: Arc<dyn Fs>, client: Arc<Client>, cx: &mut AppContext) {
 
         assistant.set_enabled(settings.enabled, cx);
     });
-    cx.observe_global::<SettingsStore>(|cx| {
+    cx.observe_global::<SettingsStore>(move |cx| {
         Assistant::update_global(cx, |assistant, cx| {
             let settings = AssistantSettings::get_global(cx);
             assistant.set_enabled(settings.enabled, cx);
         });
+
+        let new_embedding_settings = OllamaEmbeddingSettings::get(cx);
+        if new_embedding_settings != embedding_settings {
+            embedding_settings = new_embedding_settings.clone();
+            init_semantic_index(&client, new_embedding_settings, cx);
+        }
     })
     .detach();
 }
@@ -3,6 +3,7 @@ use crate::{
     humanize_token_count, parse_next_edit_suggestion,
     prompt_library::open_prompt_library,
     search::*,
+    settings_worktree_id,
     slash_command::{
         default_command::DefaultSlashCommand,
         docs_command::{DocsSlashCommand, DocsSlashCommandArgs},
@@ -12,8 +13,9 @@ use crate::{
     ApplyEdit, Assist, CompletionProvider, ConfirmCommand, Context, ContextEvent, ContextId,
     ContextStore, CycleMessageRole, DeployHistory, DeployPromptLibrary, EditSuggestion,
     InlineAssist, InlineAssistant, InsertIntoEditor, MessageStatus, ModelSelector,
-    PendingSlashCommand, PendingSlashCommandStatus, QuoteSelection, RemoteContextMetadata,
-    ResetKey, Role, SavedContextMetadata, Split, ToggleFocus, ToggleModelSelector,
+    OllamaLoadedModels, PendingSlashCommand, PendingSlashCommandStatus, QuoteSelection,
+    RemoteContextMetadata, ResetKey, Role, SavedContextMetadata, Split, ToggleFocus,
+    ToggleModelSelector,
 };
 use anyhow::{anyhow, Result};
 use assistant_slash_command::{SlashCommand, SlashCommandOutputSection};
@@ -44,10 +46,10 @@ use language::{
 };
 use multi_buffer::MultiBufferRow;
 use picker::{Picker, PickerDelegate};
-use project::{Project, ProjectLspAdapterDelegate, ProjectTransaction};
+use project::{Project, ProjectLspAdapterDelegate, ProjectTransaction, WorktreeId};
 use search::{buffer_search::DivRegistrar, BufferSearchBar};
 use settings::Settings;
-use std::{cmp, fmt::Write, ops::Range, path::PathBuf, sync::Arc, time::Duration};
+use std::{cmp, fmt::Write, mem, ops::Range, path::PathBuf, sync::Arc, time::Duration};
 use terminal_view::{terminal_panel::TerminalPanel, TerminalView};
 use theme::ThemeSettings;
 use ui::{
@@ -103,6 +105,7 @@ pub struct AssistantPanel {
     subscriptions: Vec<Subscription>,
     authentication_prompt: Option<AnyView>,
     model_selector_menu_handle: PopoverMenuHandle<ContextMenu>,
+    settings_worktree_id: Option<WorktreeId>,
 }
 
 #[derive(Clone)]
@@ -337,8 +340,12 @@ impl AssistantPanel {
             pane.toolbar().update(cx, |toolbar, cx| {
                 toolbar.add_item(cx.new_view(|_| Breadcrumbs::new()), cx);
                 toolbar.add_item(
-                    cx.new_view(|_| {
-                        ContextEditorToolbarItem::new(workspace, model_selector_menu_handle.clone())
+                    cx.new_view(|cx| {
+                        ContextEditorToolbarItem::new(
+                            workspace,
+                            model_selector_menu_handle.clone(),
+                            cx,
+                        )
                     }),
                     cx,
                 );
@@ -357,9 +364,26 @@ impl AssistantPanel {
                     prev_settings_version = CompletionProvider::global(cx).settings_version();
                 }
             }),
+            // Keep following the project's first worktree while the panel is open,
+            // not only when it is activated.
+            cx.subscribe(workspace.project(), |this, _, event, cx| {
+                if matches!(
+                    event,
+                    project::Event::WorktreeAdded | project::Event::WorktreeRemoved(_)
+                ) {
+                    this.update_settings_worktree(cx);
+                }
+            }),
+            cx.on_release(|this, _, cx| {
+                if let Some(worktree_id) = this.settings_worktree_id {
+                    cx.update_global::<CompletionProvider, _>(|provider, _| {
+                        provider.remove_worktree(worktree_id)
+                    });
+                }
+            }),
         ];
 
-        Self {
+        let mut this = Self {
             pane,
             workspace: workspace.weak_handle(),
             width: None,
@@ -371,7 +395,10 @@ impl AssistantPanel {
             subscriptions,
             authentication_prompt: None,
             model_selector_menu_handle,
-        }
+            settings_worktree_id: None,
+        };
+        this.update_settings_worktree(cx);
+        this
     }
 
     fn handle_pane_event(
@@ -412,6 +439,24 @@ impl AssistantPanel {
         }
     }
 
+    /// Keeps a provider configured with the settings of the project's first worktree,
+    /// so that a project can override the assistant settings for its own requests.
+    fn update_settings_worktree(&mut self, cx: &mut ViewContext<Self>) {
+        let worktree_id = settings_worktree_id(self.project.read(cx), cx);
+        let old_worktree_id = mem::replace(&mut self.settings_worktree_id, worktree_id);
+        if old_worktree_id == worktree_id {
+            return;
+        }
+        cx.update_global::<CompletionProvider, _>(|provider, cx| {
+            if let Some(old_worktree_id) = old_worktree_id {
+                provider.remove_worktree(old_worktree_id);
+            }
+            if let Some(worktree_id) = worktree_id {
+                provider.add_worktree(worktree_id, cx);
+            }
+        });
+    }
+
     fn completion_provider_changed(
         &mut self,
         prev_settings_version: usize,
@@ -1580,6 +1625,7 @@ impl ContextEditor {
                                     Role::User => Label::new("You").color(Color::Default),
                                     Role::Assistant => Label::new("Assistant").color(Color::Info),
                                     Role::System => Label::new("System").color(Color::Warning),
+                                    Role::Tool => Label::new("Tool").color(Color::Muted),
                                 })
                                 .tooltip(|cx| {
                                     Tooltip::with_meta(
@@ -2300,18 +2346,21 @@ pub struct ContextEditorToolbarItem {
     workspace: WeakView<Workspace>,
     active_context_editor: Option<WeakView<ContextEditor>>,
     model_selector_menu_handle: PopoverMenuHandle<ContextMenu>,
+    _subscription: Subscription,
 }
 
 impl ContextEditorToolbarItem {
     pub fn new(
         workspace: &Workspace,
         model_selector_menu_handle: PopoverMenuHandle<ContextMenu>,
+        cx: &mut ViewContext<Self>,
     ) -> Self {
         Self {
             fs: workspace.app_state().fs.clone(),
             workspace: workspace.weak_handle(),
             active_context_editor: None,
             model_selector_menu_handle,
+            _subscription: cx.observe_global::<CompletionProvider>(|_, cx| cx.notify()),
         }
     }
 
@@ -2380,17 +2429,18 @@ impl ContextEditorToolbarItem {
     }
 
     fn render_remaining_tokens(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
-        let model = CompletionProvider::global(cx).model();
         let context = &self
             .active_context_editor
             .as_ref()?
             .upgrade()?
             .read(cx)
             .context;
-        let token_count = context.read(cx).token_count()?;
+        let model =
+            CompletionProvider::global(cx).model_for_worktree(context.read(cx).worktree_id());
+        let remaining_tokens = context.read(cx).remaining_tokens()?;
         let max_token_count = model.max_token_count();
+        let token_count = max_token_count.saturating_add_signed(-remaining_tokens);
 
-        let remaining_tokens = max_token_count as isize - token_count as isize;
         let token_count_color = if remaining_tokens <= 0 {
             Color::Error
         } else if token_count as f32 / max_token_count as f32 >= 0.8 {
@@ -2426,6 +2476,7 @@ impl Render for ContextEditorToolbarItem {
                 self.fs.clone(),
             ))
             .children(self.render_remaining_tokens(cx))
+            .children(OllamaLoadedModels::new(cx))
             .child(self.render_inject_context_menu(cx))
     }
 }
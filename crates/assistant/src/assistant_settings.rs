@@ -1,9 +1,14 @@
-use std::fmt;
+use std::{fmt, mem, path::PathBuf, time::Duration};
 
 use crate::{preprocess_anthropic_request, LanguageModel, LanguageModelRequest};
 pub use anthropic::Model as AnthropicModel;
+use collections::HashMap;
 use gpui::Pixels;
-pub use ollama::Model as OllamaModel;
+use http::Url;
+pub use ollama::{
+    HttpVersion as OllamaHttpVersion, KeepAlive as OllamaKeepAlive, Model as OllamaModel,
+    ModelOptions as OllamaModelOptions, TlsConfig as OllamaTlsConfig,
+};
 pub use open_ai::Model as OpenAiModel;
 use schemars::{
     schema::{InstanceType, Metadata, Schema, SchemaObject},
@@ -185,8 +190,7 @@ pub enum AssistantProvider {
     },
     Ollama {
         model: OllamaModel,
-        api_url: String,
-        low_speed_timeout_in_seconds: Option<u64>,
+        settings: OllamaSettings,
     },
 }
 
@@ -222,11 +226,482 @@ pub enum AssistantProviderContent {
     #[serde(rename = "ollama")]
     Ollama {
         default_model: Option<OllamaModel>,
-        api_url: Option<String>,
-        low_speed_timeout_in_seconds: Option<u64>,
+        #[serde(flatten)]
+        settings: OllamaSettingsContent,
     },
 }
 
+/// How completions are spread across the Ollama servers configured in `endpoints`.
+#[derive(Copy, Clone, Default, Debug, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OllamaLoadBalance {
+    /// Use each server in turn.
+    #[default]
+    RoundRobin,
+    /// Use the server with the fewest models loaded, as reported by `/api/ps`.
+    LeastBusy,
+}
+
+/// The configuration of an Ollama model, which takes precedence over what the
+/// server reports about it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OllamaAvailableModel {
+    /// The name of the model on the server, such as `llama3.1:latest`.
+    pub name: String,
+    /// The name shown for the model in the model selector.
+    pub display_name: Option<String>,
+    /// The size of the model's context window, in tokens.
+    pub max_tokens: Option<usize>,
+    /// How long the server keeps the model loaded after a request.
+    pub keep_alive: Option<OllamaKeepAlive>,
+    /// The temperature to sample the model with when a request doesn't ask for one.
+    pub temperature: Option<f32>,
+    /// How to run the model and sample its tokens, such as `"top_k": 20` or
+    /// `"mirostat": 2`, unless a request asks otherwise.
+    #[serde(flatten)]
+    pub options: OllamaModelOptions,
+}
+
+impl OllamaAvailableModel {
+    /// Applies this configuration to a model with the same name.
+    pub fn configure(&self, model: &mut OllamaModel) {
+        if let Some(display_name) = &self.display_name {
+            model.display_name = Some(display_name.clone());
+        }
+        if let Some(max_tokens) = self.max_tokens {
+            model.max_tokens = max_tokens;
+        }
+        if let Some(keep_alive) = &self.keep_alive {
+            model.keep_alive = Some(keep_alive.clone());
+        }
+        if let Some(temperature) = self.temperature {
+            model.temperature = Some(temperature);
+        }
+        model.options = self.options.clone().or(mem::take(&mut model.options));
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OllamaSettings {
+    pub api_url: String,
+    pub low_speed_timeout_in_seconds: Option<u64>,
+    pub model_prefix: Option<String>,
+    pub tls: Option<OllamaTlsConfig>,
+    pub aliases: HashMap<String, String>,
+    pub keep_alive_indefinitely: bool,
+    pub logprobs: bool,
+    pub max_tokens_per_second: Option<u32>,
+    pub models_file: Option<PathBuf>,
+    pub num_batch: Option<usize>,
+    pub num_thread: Option<usize>,
+    pub http_version: OllamaHttpVersion,
+    pub context_shift: Option<bool>,
+    pub endpoints: Vec<String>,
+    pub load_balance: OllamaLoadBalance,
+    pub show_embedding_models: bool,
+    pub reduce_context_on_oom: bool,
+    pub markdown_safe_chunks: bool,
+    pub capture_rendered_prompt: bool,
+    pub auto_switch_for_images: bool,
+    pub hide_thinking: bool,
+    pub fallback_api_url: Option<String>,
+    pub embedding_model: Option<String>,
+    pub unload_previous_model: bool,
+    pub report_timings: bool,
+    pub max_prompt_tokens: Option<usize>,
+    pub deterministic: bool,
+    pub capture_response_headers: bool,
+    pub available_models: Vec<OllamaAvailableModel>,
+    pub refresh_interval_in_seconds: Option<u64>,
+    pub max_retries: u32,
+}
+
+impl Default for OllamaSettings {
+    fn default() -> Self {
+        Self {
+            api_url: ollama::OLLAMA_API_URL.into(),
+            low_speed_timeout_in_seconds: None,
+            model_prefix: None,
+            tls: None,
+            aliases: HashMap::default(),
+            keep_alive_indefinitely: false,
+            logprobs: false,
+            max_tokens_per_second: None,
+            models_file: None,
+            num_batch: None,
+            num_thread: None,
+            http_version: OllamaHttpVersion::default(),
+            context_shift: None,
+            endpoints: Vec::new(),
+            load_balance: OllamaLoadBalance::default(),
+            show_embedding_models: false,
+            reduce_context_on_oom: false,
+            markdown_safe_chunks: false,
+            capture_rendered_prompt: false,
+            auto_switch_for_images: false,
+            hide_thinking: false,
+            fallback_api_url: None,
+            embedding_model: None,
+            unload_previous_model: false,
+            report_timings: false,
+            max_prompt_tokens: None,
+            deterministic: false,
+            capture_response_headers: false,
+            available_models: Vec::new(),
+            refresh_interval_in_seconds: None,
+            max_retries: 2,
+        }
+    }
+}
+
+impl OllamaSettings {
+    fn merge(&mut self, content: OllamaSettingsContent) {
+        merge(&mut self.api_url, content.api_url);
+        if let Some(low_speed_timeout_in_seconds) = content.low_speed_timeout_in_seconds {
+            self.low_speed_timeout_in_seconds = Some(low_speed_timeout_in_seconds);
+        }
+        merge(&mut self.model_prefix, content.model_prefix.map(Some));
+        merge(&mut self.tls, content.tls.map(Some));
+        merge(&mut self.aliases, content.aliases);
+        merge(
+            &mut self.keep_alive_indefinitely,
+            content.keep_alive_indefinitely,
+        );
+        merge(&mut self.logprobs, content.logprobs);
+        merge(
+            &mut self.max_tokens_per_second,
+            content.max_tokens_per_second.map(Some),
+        );
+        merge(&mut self.models_file, content.models_file.map(Some));
+        merge(&mut self.num_batch, content.num_batch.map(Some));
+        merge(&mut self.num_thread, content.num_thread.map(Some));
+        merge(&mut self.http_version, content.http_version);
+        merge(&mut self.context_shift, content.context_shift.map(Some));
+        merge(&mut self.endpoints, content.endpoints);
+        merge(&mut self.load_balance, content.load_balance);
+        merge(
+            &mut self.show_embedding_models,
+            content.show_embedding_models,
+        );
+        merge(
+            &mut self.reduce_context_on_oom,
+            content.reduce_context_on_oom,
+        );
+        merge(&mut self.markdown_safe_chunks, content.markdown_safe_chunks);
+        merge(
+            &mut self.capture_rendered_prompt,
+            content.capture_rendered_prompt,
+        );
+        merge(
+            &mut self.auto_switch_for_images,
+            content.auto_switch_for_images,
+        );
+        merge(&mut self.hide_thinking, content.hide_thinking);
+        merge(
+            &mut self.fallback_api_url,
+            content.fallback_api_url.map(Some),
+        );
+        merge(&mut self.embedding_model, content.embedding_model.map(Some));
+        merge(
+            &mut self.unload_previous_model,
+            content.unload_previous_model,
+        );
+        merge(&mut self.report_timings, content.report_timings);
+        merge(
+            &mut self.max_prompt_tokens,
+            content.max_prompt_tokens.map(Some),
+        );
+        merge(&mut self.deterministic, content.deterministic);
+        merge(
+            &mut self.capture_response_headers,
+            content.capture_response_headers,
+        );
+        merge(&mut self.available_models, content.available_models);
+        merge(
+            &mut self.refresh_interval_in_seconds,
+            content.refresh_interval_in_seconds.map(Some),
+        );
+        merge(&mut self.max_retries, content.max_retries);
+    }
+
+    /// Returns the models listed by the server with the configured
+    /// `available_models` applied, followed by the configured models the server
+    /// didn't list.
+    pub fn with_available_models(&self, mut models: Vec<OllamaModel>) -> Vec<OllamaModel> {
+        for configured in &self.available_models {
+            match models
+                .iter_mut()
+                .find(|model| model.name == configured.name)
+            {
+                Some(model) => configured.configure(model),
+                None => {
+                    let mut model = OllamaModel::new(&configured.name);
+                    configured.configure(&mut model);
+                    models.push(model);
+                }
+            }
+        }
+        models
+    }
+
+    /// Returns the model as it is identified to the rest of Zed, namespaced with
+    /// the configured `model_prefix`.
+    pub fn namespaced_model(&self, model: &OllamaModel) -> OllamaModel {
+        let mut model = model.clone();
+        if let Some(prefix) = self.model_prefix.as_deref() {
+            if !model.name.is_empty() && !model.name.starts_with(prefix) {
+                model.name = format!("{prefix}{}", model.name);
+            }
+        }
+        model
+    }
+
+    /// Returns the model as it is known to the Ollama server, with the configured
+    /// `model_prefix` removed.
+    pub fn server_model(&self, mut model: OllamaModel) -> OllamaModel {
+        if let Some(prefix) = self.model_prefix.as_deref() {
+            if let Some(name) = model.name.strip_prefix(prefix) {
+                model.name = name.to_string();
+            }
+        }
+        model
+    }
+
+    /// Resolves a model requested by the rest of Zed to the model the Ollama server
+    /// should run, following the configured aliases when the requested model is not
+    /// among the `available` ones.
+    pub fn resolve_model(&self, model: OllamaModel, available: &[OllamaModel]) -> OllamaModel {
+        let mut model = self.server_model(model);
+        if !available
+            .iter()
+            .any(|available| available.name == model.name)
+        {
+            if let Some(alias) = self.aliases.get(&model.name) {
+                model.name = alias.clone();
+            }
+        }
+        if let Some(configured) = self
+            .available_models
+            .iter()
+            .find(|configured| configured.name == model.name)
+        {
+            configured.configure(&mut model);
+        }
+        model
+    }
+
+    pub fn low_speed_timeout(&self) -> Option<Duration> {
+        self.low_speed_timeout_in_seconds.map(Duration::from_secs)
+    }
+
+    pub fn refresh_interval(&self) -> Option<Duration> {
+        self.refresh_interval_in_seconds
+            .filter(|seconds| *seconds > 0)
+            .map(Duration::from_secs)
+    }
+
+    /// Returns a copy of these settings that is safe to include in a bug report,
+    /// with any credentials embedded in the configured URLs replaced.
+    pub fn redacted(&self) -> Self {
+        Self {
+            api_url: redact_url_credentials(&self.api_url),
+            endpoints: self
+                .endpoints
+                .iter()
+                .map(|endpoint| redact_url_credentials(endpoint))
+                .collect(),
+            fallback_api_url: self.fallback_api_url.as_deref().map(redact_url_credentials),
+            ..self.clone()
+        }
+    }
+}
+
+fn redact_url_credentials(url: &str) -> String {
+    const REDACTED: &str = "REDACTED";
+
+    let Ok(mut parsed) = Url::parse(url) else {
+        return url.to_string();
+    };
+    if parsed.username().is_empty() && parsed.password().is_none() {
+        return url.to_string();
+    }
+    if !parsed.username().is_empty() {
+        parsed.set_username(REDACTED).ok();
+    }
+    if parsed.password().is_some() {
+        parsed.set_password(Some(REDACTED)).ok();
+    }
+    parsed.into()
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OllamaSettingsContent {
+    /// The URL of the Ollama server.
+    ///
+    /// Default: http://localhost:11434
+    pub api_url: Option<String>,
+    /// The number of seconds after which a stalled response is considered to have timed out.
+    pub low_speed_timeout_in_seconds: Option<u64>,
+    /// A prefix prepended to the ids of Ollama models, such as `local:`, to tell
+    /// them apart from same-named models offered by other providers.
+    ///
+    /// Default: none
+    pub model_prefix: Option<String>,
+    /// TLS options for connecting to an Ollama server over https, such as a
+    /// custom CA bundle for servers behind a private certificate authority.
+    ///
+    /// Default: standard certificate verification
+    pub tls: Option<OllamaTlsConfig>,
+    /// Maps model ids that are no longer available, for example after a model was
+    /// renamed or re-tagged, to the model that should be used in their place.
+    ///
+    /// Default: {}
+    pub aliases: Option<HashMap<String, String>>,
+    /// Whether to keep models loaded until another model is loaded, overriding the
+    /// `keep_alive` configured for individual models. Models without a `keep_alive`
+    /// of their own are already kept loaded.
+    ///
+    /// Default: false
+    pub keep_alive_indefinitely: Option<bool>,
+    /// Whether to ask Ollama for the log probability of every generated token and
+    /// write them to the log. Has no effect on Ollama versions that don't report
+    /// them.
+    ///
+    /// Default: false
+    pub logprobs: Option<bool>,
+    /// The most tokens per second to generate, so that a single client doesn't take
+    /// more than its share of a server used by several people.
+    ///
+    /// Default: none
+    pub max_tokens_per_second: Option<u32>,
+    /// A JSON file listing the models the server offers, such as
+    /// `[{ "name": "llama3:latest", "max_tokens": 8192 }]`. When set, the models
+    /// are read from this file instead of being requested from the server, for
+    /// setups where only the completion endpoints are reachable.
+    ///
+    /// Default: none
+    pub models_file: Option<PathBuf>,
+    /// How many prompt tokens to process at once. Larger batches shorten the time
+    /// to the first token on long prompts, given enough memory.
+    ///
+    /// Default: chosen by the Ollama server
+    pub num_batch: Option<usize>,
+    /// How many threads the Ollama server uses for generation.
+    ///
+    /// Default: chosen by the Ollama server
+    pub num_thread: Option<usize>,
+    /// The HTTP version used to stream completions. Set this to `http1` when a
+    /// reverse proxy in front of the server holds back streamed HTTP/2 responses.
+    ///
+    /// Default: auto
+    pub http_version: Option<OllamaHttpVersion>,
+    /// What to do when a conversation no longer fits in the model's context window:
+    /// `true` drops its oldest part to make room, `false` fails the request so that
+    /// the conversation can be trimmed deliberately instead.
+    ///
+    /// Default: chosen by the Ollama server
+    pub context_shift: Option<bool>,
+    /// The URLs of further Ollama servers running the same models as the one at
+    /// `api_url`, to spread completions across. Models are still listed and pulled
+    /// using `api_url`.
+    ///
+    /// Default: []
+    pub endpoints: Option<Vec<String>>,
+    /// How to choose the server for each completion when `endpoints` are configured.
+    ///
+    /// Default: round_robin
+    pub load_balance: Option<OllamaLoadBalance>,
+    /// Whether to list embedding models, whose names contain `-embed`, among the
+    /// models to chat with. They are hidden by default as most of them can't chat.
+    ///
+    /// Default: false
+    pub show_embedding_models: Option<bool>,
+    /// Whether to retry a completion once with half the context size when the
+    /// Ollama server runs out of memory loading the model, instead of failing.
+    ///
+    /// Default: false
+    pub reduce_context_on_oom: Option<bool>,
+    /// Whether to hold back streamed text until a code block or inline code span
+    /// that it opens is closed, so that partial responses never render with half
+    /// of their markdown formatting.
+    ///
+    /// Default: false
+    pub markdown_safe_chunks: Option<bool>,
+    /// Whether to ask Ollama for the prompt it renders from the conversation with
+    /// the model's chat template before every completion and write it to the log,
+    /// to debug models that misread the conversation. Needs a version of Ollama
+    /// that supports this.
+    ///
+    /// Default: false
+    pub capture_rendered_prompt: Option<bool>,
+    /// Whether to send requests with images attached to an installed model that
+    /// supports vision when the selected model doesn't, instead of failing.
+    ///
+    /// Default: false
+    pub auto_switch_for_images: Option<bool>,
+    /// Whether to leave the reasoning that models like DeepSeek-R1 write between
+    /// `<think>` tags before answering out of the response, so that only the answer
+    /// is shown. The reasoning still counts towards token usage.
+    ///
+    /// Default: false
+    pub hide_thinking: Option<bool>,
+    /// The URL of an Ollama server to use instead of `api_url` while it can't be
+    /// reached, such as a local server for when an office server is out of reach.
+    ///
+    /// Default: none
+    pub fallback_api_url: Option<String>,
+    /// The model to embed text with, such as `nomic-embed-text`, for building a
+    /// semantic index of a project.
+    ///
+    /// Default: none
+    pub embedding_model: Option<String>,
+    /// Whether to unload the previously selected model before loading a newly
+    /// selected one, so that both never take up memory at the same time.
+    ///
+    /// Default: false
+    pub unload_previous_model: Option<bool>,
+    /// Whether to log how long a completion spent loading the model, evaluating the
+    /// prompt and generating the response, for diagnosing slow completions.
+    ///
+    /// Default: false
+    pub report_timings: Option<bool>,
+    /// The largest prompt, in estimated tokens, to send to the server. Larger
+    /// requests fail without reaching the server, to protect a shared server
+    /// regardless of the model's context window.
+    ///
+    /// Default: none
+    pub max_prompt_tokens: Option<usize>,
+    /// Whether to always pick the most likely token, ignoring the temperature of
+    /// requests, so that the same prompt gets the same response.
+    ///
+    /// Default: false
+    pub deterministic: Option<bool>,
+    /// Whether to log the headers of the server's responses to completion requests,
+    /// for diagnosing proxies and gateways in front of the server.
+    /// The values of headers that carry credentials are redacted.
+    ///
+    /// Default: false
+    pub capture_response_headers: Option<bool>,
+    /// Models to configure, such as `[{ "name": "llama3.1:latest", "max_tokens":
+    /// 131072 }]` to use the full context window of a model that supports one
+    /// larger than the server's default. Models the server doesn't list are added.
+    ///
+    /// Default: []
+    pub available_models: Option<Vec<OllamaAvailableModel>>,
+    /// How often, in seconds, to check the server for models pulled or removed
+    /// outside of Zed and for the models it has loaded, noticing when it goes down
+    /// or comes back up. Unset or zero only checks when the assistant connects.
+    ///
+    /// Default: none
+    pub refresh_interval_in_seconds: Option<u64>,
+    /// How many times to send a request again, waiting longer each time, when the
+    /// server can't be reached, times out or fails with a server error. Requests
+    /// are only retried until the response starts streaming.
+    ///
+    /// Default: 2
+    pub max_retries: Option<u32>,
+}
+
 #[derive(Debug, Default)]
 pub struct AssistantSettings {
     pub enabled: bool,
@@ -370,8 +845,7 @@ impl AssistantSettingsContent {
                         LanguageModel::Ollama(model) => {
                             *provider = Some(AssistantProviderContent::Ollama {
                                 default_model: Some(model),
-                                api_url: None,
-                                low_speed_timeout_in_seconds: None,
+                                settings: Default::default(),
                             })
                         }
                     },
@@ -522,25 +996,14 @@ impl Settings for AssistantSettings {
                         }
                     }
                     (
-                        AssistantProvider::Ollama {
-                            model,
-                            api_url,
-                            low_speed_timeout_in_seconds,
-                        },
+                        AssistantProvider::Ollama { model, settings },
                         AssistantProviderContent::Ollama {
                             default_model: model_override,
-                            api_url: api_url_override,
-                            low_speed_timeout_in_seconds: low_speed_timeout_in_seconds_override,
+                            settings: settings_override,
                         },
                     ) => {
                         merge(model, model_override);
-                        merge(api_url, api_url_override);
-                        if let Some(low_speed_timeout_in_seconds_override) =
-                            low_speed_timeout_in_seconds_override
-                        {
-                            *low_speed_timeout_in_seconds =
-                                Some(low_speed_timeout_in_seconds_override);
-                        }
+                        settings.merge(settings_override);
                     }
                     (
                         AssistantProvider::Anthropic {
@@ -593,13 +1056,15 @@ impl Settings for AssistantSettings {
                             },
                             AssistantProviderContent::Ollama {
                                 default_model: model,
-                                api_url,
-                                low_speed_timeout_in_seconds,
-                            } => AssistantProvider::Ollama {
-                                model: model.unwrap_or_default(),
-                                api_url: api_url.unwrap_or_else(|| ollama::OLLAMA_API_URL.into()),
-                                low_speed_timeout_in_seconds,
-                            },
+                                settings: settings_content,
+                            } => {
+                                let mut settings = OllamaSettings::default();
+                                settings.merge(settings_content);
+                                AssistantProvider::Ollama {
+                                    model: model.unwrap_or_default(),
+                                    settings,
+                                }
+                            }
                         };
                     }
                 }
@@ -708,4 +1173,88 @@ mod tests {
             }
         );
     }
+
+    #[gpui::test]
+    fn test_deserialize_ollama_tls_settings(cx: &mut AppContext) {
+        let store = settings::SettingsStore::test(cx);
+        cx.set_global(store);
+        AssistantSettings::register(cx);
+
+        SettingsStore::update_global(cx, |store, cx| {
+            store
+                .set_user_settings(
+                    r#"{
+                        "assistant": {
+                            "version": "1",
+                            "provider": {
+                                "name": "ollama",
+                                "api_url": "https://ollama.internal.example.com",
+                                "tls": {
+                                    "ca_bundle_path": "/etc/ssl/certs/corp-ca.pem"
+                                }
+                            }
+                        }
+                    }"#,
+                    cx,
+                )
+                .unwrap();
+        });
+        assert_eq!(
+            AssistantSettings::get_global(cx).provider,
+            AssistantProvider::Ollama {
+                model: OllamaModel::default(),
+                settings: OllamaSettings {
+                    api_url: "https://ollama.internal.example.com".into(),
+                    tls: Some(OllamaTlsConfig {
+                        ca_bundle_path: Some("/etc/ssl/certs/corp-ca.pem".into()),
+                        danger_accept_invalid_certs: false,
+                    }),
+                    ..Default::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_ollama_model_schema_omits_server_metadata() {
+        let schema = serde_json::to_value(schemars::schema_for!(OllamaModel)).unwrap();
+        let properties = schema["properties"].as_object().unwrap();
+        assert!(properties.contains_key("max_tokens"));
+        for field in ["metadata", "digest", "family", "context_length"] {
+            assert!(!properties.contains_key(field), "{field}");
+        }
+    }
+
+    #[test]
+    fn test_deserialize_ollama_settings_content() {
+        let cases = [
+            (serde_json::json!({}), OllamaSettings::default()),
+            (
+                serde_json::json!({ "reduce_context_on_oom": true }),
+                OllamaSettings {
+                    reduce_context_on_oom: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                serde_json::json!({ "markdown_safe_chunks": true }),
+                OllamaSettings {
+                    markdown_safe_chunks: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                serde_json::json!({ "capture_rendered_prompt": true }),
+                OllamaSettings {
+                    capture_rendered_prompt: true,
+                    ..Default::default()
+                },
+            ),
+        ];
+        for (content, expected) in cases {
+            let mut settings = OllamaSettings::default();
+            settings.merge(serde_json::from_value(content.clone()).unwrap());
+            assert_eq!(settings, expected, "{content}");
+        }
+    }
 }
@@ -139,6 +139,10 @@ impl LanguageModelCompletionProvider for AnthropicCompletionProvider {
         .boxed()
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -199,7 +203,7 @@ impl AnthropicCompletionProvider {
                 .iter()
                 .map(|msg| RequestMessage {
                     role: match msg.role {
-                        Role::User => anthropic::Role::User,
+                        Role::User | Role::Tool => anthropic::Role::User,
                         Role::Assistant => anthropic::Role::Assistant,
                         Role::System => unreachable!("filtered out by preprocess_request"),
                     },
@@ -223,16 +227,29 @@ pub fn preprocess_anthropic_request(request: &mut LanguageModelRequest) {
         }
 
         match message.role {
-            Role::User | Role::Assistant => {
+            Role::User | Role::Assistant | Role::Tool => {
+                // Anthropic only accepts tool results that carry the id of a tool call
+                // made in the same conversation, which requests don't track, so
+                // they're sent as plain user text.
+                let role = match message.role {
+                    Role::Tool => Role::User,
+                    role => role,
+                };
                 if let Some(last_message) = new_messages.last_mut() {
-                    if last_message.role == message.role {
+                    if last_message.role == role {
                         last_message.content.push_str("\n\n");
                         last_message.content.push_str(&message.content);
+                        last_message.images.extend(message.images);
                         continue;
                     }
                 }
 
-                new_messages.push(message);
+                new_messages.push(LanguageModelRequestMessage {
+                    role,
+                    content: message.content,
+                    images: message.images,
+                    tool_name: None,
+                });
             }
             Role::System => {
                 if !system_message.is_empty() {
@@ -249,6 +266,8 @@ pub fn preprocess_anthropic_request(request: &mut LanguageModelRequest) {
             LanguageModelRequestMessage {
                 role: Role::System,
                 content: system_message,
+                images: Vec::new(),
+                tool_name: None,
             },
         );
     }
@@ -365,3 +384,41 @@ impl Render for AuthenticationPrompt {
             .into_any()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preprocess_tool_results() {
+        let message = |role, content: &str| LanguageModelRequestMessage {
+            role,
+            content: content.into(),
+            images: Vec::new(),
+            tool_name: None,
+        };
+        let mut request = LanguageModelRequest {
+            messages: vec![
+                message(Role::System, "Be brief."),
+                message(Role::User, "What's the weather in Paris?"),
+                message(Role::Tool, r#"{"temperature":22}"#),
+                message(Role::Assistant, "It's 22°C."),
+            ],
+            ..Default::default()
+        };
+        preprocess_anthropic_request(&mut request);
+
+        // Tool results are merged into the user's turn as plain text.
+        assert_eq!(
+            request.messages,
+            [
+                message(Role::System, "Be brief."),
+                message(
+                    Role::User,
+                    "What's the weather in Paris?\n\n{\"temperature\":22}"
+                ),
+                message(Role::Assistant, "It's 22°C."),
+            ]
+        );
+    }
+}
@@ -149,7 +149,7 @@ impl LanguageModelCompletionProvider for CloudCompletionProvider {
                 .map(|message| message.to_proto())
                 .collect(),
             stop: request.stop,
-            temperature: request.temperature,
+            temperature: request.temperature.unwrap_or(1.0),
             tools: Vec::new(),
             tool_choice: None,
         };
@@ -169,6 +169,10 @@ impl LanguageModelCompletionProvider for CloudCompletionProvider {
             .boxed()
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
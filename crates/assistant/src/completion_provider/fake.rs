@@ -2,7 +2,10 @@ use anyhow::Result;
 use collections::HashMap;
 use futures::{channel::mpsc, future::BoxFuture, stream::BoxStream, FutureExt, StreamExt};
 use gpui::{AnyView, AppContext, Task};
-use std::sync::Arc;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Arc,
+};
 use ui::WindowContext;
 
 use crate::{LanguageModel, LanguageModelCompletionProvider, LanguageModelRequest};
@@ -10,6 +13,8 @@ use crate::{LanguageModel, LanguageModelCompletionProvider, LanguageModelRequest
 #[derive(Clone, Default)]
 pub struct FakeCompletionProvider {
     current_completion_txs: Arc<parking_lot::Mutex<HashMap<String, mpsc::UnboundedSender<String>>>>,
+    non_streaming: Arc<AtomicBool>,
+    model: Arc<parking_lot::Mutex<LanguageModel>>,
 }
 
 impl FakeCompletionProvider {
@@ -23,6 +28,15 @@ impl FakeCompletionProvider {
         this
     }
 
+    pub fn set_supports_streaming(&self, supports_streaming: bool) {
+        self.non_streaming
+            .store(!supports_streaming, Ordering::SeqCst);
+    }
+
+    pub fn set_model(&self, model: LanguageModel) {
+        *self.model.lock() = model;
+    }
+
     pub fn running_completions(&self) -> Vec<LanguageModelRequest> {
         self.current_completion_txs
             .lock()
@@ -54,7 +68,11 @@ impl FakeCompletionProvider {
 
 impl LanguageModelCompletionProvider for FakeCompletionProvider {
     fn available_models(&self, _cx: &AppContext) -> Vec<LanguageModel> {
-        vec![LanguageModel::default()]
+        vec![self.model()]
+    }
+
+    fn supports_streaming(&self) -> bool {
+        !self.non_streaming.load(Ordering::SeqCst)
     }
 
     fn settings_version(&self) -> usize {
@@ -78,7 +96,7 @@ impl LanguageModelCompletionProvider for FakeCompletionProvider {
     }
 
     fn model(&self) -> LanguageModel {
-        LanguageModel::default()
+        self.model.lock().clone()
     }
 
     fn count_tokens(
@@ -100,6 +118,10 @@ impl LanguageModelCompletionProvider for FakeCompletionProvider {
         async move { Ok(rx.map(Ok).boxed()) }.boxed()
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -2,7 +2,8 @@ use crate::assistant_settings::CloudModel;
 use crate::assistant_settings::{AssistantProvider, AssistantSettings};
 use crate::LanguageModelCompletionProvider;
 use crate::{
-    assistant_settings::OpenAiModel, CompletionProvider, LanguageModel, LanguageModelRequest, Role,
+    assistant_settings::OpenAiModel, count_tokens_in_background, CompletionProvider, LanguageModel,
+    LanguageModelRequest, Role, TiktokenCounter,
 };
 use anyhow::{anyhow, Result};
 use editor::{Editor, EditorElement, EditorStyle};
@@ -70,7 +71,10 @@ impl OpenAiCompletionProvider {
                 .messages
                 .into_iter()
                 .map(|msg| match msg.role {
-                    Role::User => RequestMessage::User {
+                    // OpenAI requires tool results to carry the id of the call they
+                    // answer, which requests don't track, so they're sent as plain
+                    // user text.
+                    Role::User | Role::Tool => RequestMessage::User {
                         content: msg.content,
                     },
                     Role::Assistant => RequestMessage::Assistant {
@@ -84,7 +88,7 @@ impl OpenAiCompletionProvider {
                 .collect(),
             stream: true,
             stop: request.stop,
-            temperature: request.temperature,
+            temperature: request.temperature.unwrap_or(1.0),
             tools: Vec::new(),
             tool_choice: None,
         }
@@ -212,6 +216,10 @@ impl LanguageModelCompletionProvider for OpenAiCompletionProvider {
         .boxed()
     }
 
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
+
     fn as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self
     }
@@ -221,44 +229,26 @@ pub fn count_open_ai_tokens(
     request: LanguageModelRequest,
     background_executor: &gpui::BackgroundExecutor,
 ) -> BoxFuture<'static, Result<usize>> {
-    background_executor
-        .spawn(async move {
-            let messages = request
-                .messages
-                .into_iter()
-                .map(|message| tiktoken_rs::ChatCompletionRequestMessage {
-                    role: match message.role {
-                        Role::User => "user".into(),
-                        Role::Assistant => "assistant".into(),
-                        Role::System => "system".into(),
-                    },
-                    content: Some(message.content),
-                    name: None,
-                    function_call: None,
-                })
-                .collect::<Vec<_>>();
-
-            match request.model {
-                LanguageModel::Anthropic(_)
-                | LanguageModel::Cloud(CloudModel::Claude3_5Sonnet)
-                | LanguageModel::Cloud(CloudModel::Claude3Opus)
-                | LanguageModel::Cloud(CloudModel::Claude3Sonnet)
-                | LanguageModel::Cloud(CloudModel::Claude3Haiku)
-                | LanguageModel::OpenAi(OpenAiModel::Custom { .. }) => {
-                    // Tiktoken doesn't yet support these models, so we manually use the
-                    // same tokenizer as GPT-4.
-                    tiktoken_rs::num_tokens_from_messages("gpt-4", &messages)
-                }
-                _ => tiktoken_rs::num_tokens_from_messages(request.model.id(), &messages),
-            }
-        })
-        .boxed()
+    let counter = match &request.model {
+        LanguageModel::Anthropic(_)
+        | LanguageModel::Cloud(CloudModel::Claude3_5Sonnet)
+        | LanguageModel::Cloud(CloudModel::Claude3Opus)
+        | LanguageModel::Cloud(CloudModel::Claude3Sonnet)
+        | LanguageModel::Cloud(CloudModel::Claude3Haiku)
+        | LanguageModel::OpenAi(OpenAiModel::Custom { .. }) => {
+            // Tiktoken doesn't yet support these models, so we manually use the
+            // same tokenizer as GPT-4.
+            TiktokenCounter::new("gpt-4")
+        }
+        model => TiktokenCounter::new(model.id()),
+    };
+    count_tokens_in_background(Arc::new(counter), request.messages, background_executor)
 }
 
 impl From<Role> for open_ai::Role {
     fn from(val: Role) -> Self {
         match val {
-            Role::User => OpenAiRole::User,
+            Role::User | Role::Tool => OpenAiRole::User,
             Role::Assistant => OpenAiRole::Assistant,
             Role::System => OpenAiRole::System,
         }
@@ -376,3 +366,36 @@ impl Render for AuthenticationPrompt {
             .into_any()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::LanguageModelRequestMessage;
+    use http::FakeHttpClient;
+
+    #[test]
+    fn test_tool_results_are_sent_as_user_messages() {
+        let provider = OpenAiCompletionProvider::new(
+            OpenAiModel::FourOmni,
+            open_ai::OPEN_AI_API_URL.into(),
+            FakeHttpClient::with_404_response(),
+            None,
+            0,
+        );
+        let request = provider.to_open_ai_request(LanguageModelRequest {
+            messages: vec![LanguageModelRequestMessage {
+                role: Role::Tool,
+                content: r#"{"temperature":22}"#.into(),
+                images: Vec::new(),
+                tool_name: Some("get_temperature".into()),
+            }],
+            ..Default::default()
+        });
+        assert_eq!(
+            request.messages,
+            [RequestMessage::User {
+                content: r#"{"temperature":22}"#.into()
+            }]
+        );
+    }
+}
@@ -0,0 +1,122 @@
+use crate::{LanguageModelRequestMessage, Role};
+use anyhow::Result;
+use futures::{future::BoxFuture, FutureExt};
+use std::sync::Arc;
+
+/// Counts the tokens a model splits the messages of a request into.
+pub trait TokenCounter: Send + Sync {
+    fn count_tokens(&self, messages: &[LanguageModelRequestMessage]) -> Result<usize>;
+}
+
+/// Counts tokens with the tokenizer of an OpenAI model, including the tokens that
+/// delimit every message.
+pub struct TiktokenCounter {
+    model: String,
+}
+
+impl TiktokenCounter {
+    pub fn new(model: impl Into<String>) -> Self {
+        Self {
+            model: model.into(),
+        }
+    }
+}
+
+impl TokenCounter for TiktokenCounter {
+    fn count_tokens(&self, messages: &[LanguageModelRequestMessage]) -> Result<usize> {
+        let messages = messages
+            .iter()
+            .map(|message| tiktoken_rs::ChatCompletionRequestMessage {
+                role: match message.role {
+                    Role::User => "user".into(),
+                    Role::Assistant => "assistant".into(),
+                    Role::System => "system".into(),
+                    Role::Tool => "tool".into(),
+                },
+                content: Some(message.content.clone()),
+                name: None,
+                function_call: None,
+            })
+            .collect::<Vec<_>>();
+        tiktoken_rs::num_tokens_from_messages(&self.model, &messages)
+    }
+}
+
+/// Estimates tokens as one for every four characters, for models whose tokenizer
+/// isn't known.
+pub struct CharacterCountEstimate;
+
+impl TokenCounter for CharacterCountEstimate {
+    fn count_tokens(&self, messages: &[LanguageModelRequestMessage]) -> Result<usize> {
+        Ok(messages
+            .iter()
+            .map(|message| message.content.chars().count())
+            .sum::<usize>()
+            / 4)
+    }
+}
+
+/// Model families, as reported by Ollama, whose tokens OpenAI's `cl100k_base`
+/// vocabulary approximates better than counting characters does. The counts are
+/// only approximate: llama3 and qwen2 use byte-level BPE vocabularies close to
+/// `cl100k_base`, but Ollama also reports the `llama` family for llama2, codellama
+/// and mistral, which use SentencePiece vocabularies.
+const CL100K_FAMILIES: &[&str] = &["llama", "qwen2", "qwen2moe"];
+
+/// Returns the token counter for models of the given family, falling back to an
+/// estimate from the number of characters for unknown families.
+pub fn token_counter_for_family(family: Option<&str>) -> Arc<dyn TokenCounter> {
+    match family {
+        Some(family) if CL100K_FAMILIES.contains(&family) => {
+            Arc::new(TiktokenCounter::new("gpt-4"))
+        }
+        _ => Arc::new(CharacterCountEstimate),
+    }
+}
+
+/// Counts the tokens of `messages` on the background executor, since loading a
+/// tokenizer's vocabulary takes a while.
+pub fn count_tokens_in_background(
+    counter: Arc<dyn TokenCounter>,
+    messages: Vec<LanguageModelRequestMessage>,
+    background_executor: &gpui::BackgroundExecutor,
+) -> BoxFuture<'static, Result<usize>> {
+    background_executor
+        .spawn(async move { counter.count_tokens(&messages) })
+        .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_counter_for_family() {
+        let messages = |content: &str| {
+            [LanguageModelRequestMessage {
+                role: Role::User,
+                content: content.into(),
+                images: Vec::new(),
+                tool_name: None,
+            }]
+        };
+
+        assert_eq!(
+            token_counter_for_family(Some("gemma"))
+                .count_tokens(&messages(
+                    "fn main() {\n    println!(\"Hello, world!\");\n}\n"
+                ))
+                .unwrap(),
+            11
+        );
+        // "hello" and " world" are a token each, plus three tokens delimiting the
+        // message, one for its role and three priming the reply.
+        assert_eq!(
+            token_counter_for_family(Some("llama"))
+                .count_tokens(&messages("hello world"))
+                .unwrap(),
+            9
+        );
+        assert_eq!(token_counter_for_family(None).count_tokens(&[]).unwrap(), 0);
+    }
+}
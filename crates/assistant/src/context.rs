@@ -1,6 +1,6 @@
 use crate::{
-    slash_command::SlashCommandLine, CompletionProvider, LanguageModelRequest,
-    LanguageModelRequestMessage, MessageId, MessageStatus, Role,
+    slash_command::SlashCommandLine, CompletionProvider, LanguageModelOptions,
+    LanguageModelRequest, LanguageModelRequestMessage, MessageId, MessageStatus, Role,
 };
 use anyhow::{anyhow, Context as _, Result};
 use assistant_slash_command::{
@@ -15,6 +15,7 @@ use gpui::{AppContext, Context as _, EventEmitter, Model, ModelContext, Subscrip
 use language::{AnchorRangeExt, Bias, Buffer, LanguageRegistry, OffsetRangeExt, Point, ToOffset};
 use open_ai::Model as OpenAiModel;
 use paths::contexts_dir;
+use project::WorktreeId;
 use serde::{Deserialize, Serialize};
 use std::{
     cmp::Ordering,
@@ -314,6 +315,8 @@ impl Message {
         LanguageModelRequestMessage {
             role: self.role,
             content: buffer.text_for_range(self.offset_range.clone()).collect(),
+            images: Vec::new(),
+            tool_name: None,
         }
     }
 }
@@ -344,11 +347,13 @@ pub struct Context {
     pending_summary: Task<Option<()>>,
     completion_count: usize,
     pending_completions: Vec<PendingCompletion>,
-    token_count: Option<usize>,
+    remaining_tokens: Option<isize>,
     pending_token_count: Task<Option<()>>,
     pending_edit_suggestion_parse: Option<Task<()>>,
     pending_save: Task<Result<()>>,
     path: Option<PathBuf>,
+    worktree_id: Option<WorktreeId>,
+    model_options: LanguageModelOptions,
     _subscriptions: Vec<Subscription>,
     telemetry: Option<Arc<Telemetry>>,
     language_registry: Arc<LanguageRegistry>,
@@ -409,12 +414,14 @@ impl Context {
             pending_summary: Task::ready(None),
             completion_count: Default::default(),
             pending_completions: Default::default(),
-            token_count: None,
+            remaining_tokens: None,
             pending_token_count: Task::ready(None),
             pending_edit_suggestion_parse: None,
             _subscriptions: vec![cx.subscribe(&buffer, Self::handle_buffer_event)],
             pending_save: Task::ready(Ok(())),
             path: None,
+            worktree_id: None,
+            model_options: LanguageModelOptions::default(),
             buffer,
             telemetry,
             language_registry,
@@ -779,8 +786,8 @@ impl Context {
         }
     }
 
-    pub(crate) fn token_count(&self) -> Option<usize> {
-        self.token_count
+    pub(crate) fn remaining_tokens(&self) -> Option<isize> {
+        self.remaining_tokens
     }
 
     pub(crate) fn count_remaining_tokens(&mut self, cx: &mut ModelContext<Self>) {
@@ -791,12 +798,12 @@ impl Context {
                     .timer(Duration::from_millis(200))
                     .await;
 
-                let token_count = cx
-                    .update(|cx| CompletionProvider::global(cx).count_tokens(request, cx))?
+                let remaining_tokens = cx
+                    .update(|cx| CompletionProvider::global(cx).remaining_tokens(request, cx))?
                     .await?;
 
                 this.update(&mut cx, |this, cx| {
-                    this.token_count = Some(token_count);
+                    this.remaining_tokens = Some(remaining_tokens);
                     cx.notify()
                 })?;
                 anyhow::Ok(())
@@ -1224,7 +1231,8 @@ impl Context {
                         });
 
                         if let Some(telemetry) = this.telemetry.as_ref() {
-                            let model = CompletionProvider::global(cx).model();
+                            let model =
+                                CompletionProvider::global(cx).model_for_worktree(this.worktree_id);
                             telemetry.report_assistant_event(
                                 Some(this.id.0.clone()),
                                 AssistantKind::Panel,
@@ -1247,6 +1255,26 @@ impl Context {
         user_messages
     }
 
+    /// Sets the worktree whose settings configure the provider that completes this
+    /// context.
+    pub fn set_worktree_id(&mut self, worktree_id: Option<WorktreeId>) {
+        self.worktree_id = worktree_id;
+    }
+
+    pub fn worktree_id(&self) -> Option<WorktreeId> {
+        self.worktree_id
+    }
+
+    /// Sets the options to sample the responses to this context with, overriding
+    /// the options the model was configured with.
+    pub fn set_model_options(&mut self, model_options: LanguageModelOptions) {
+        self.model_options = model_options;
+    }
+
+    pub fn model_options(&self) -> &LanguageModelOptions {
+        &self.model_options
+    }
+
     pub fn to_completion_request(&self, cx: &AppContext) -> LanguageModelRequest {
         let messages = self
             .messages(cx)
@@ -1254,10 +1282,15 @@ impl Context {
             .map(|message| message.to_request_message(self.buffer.read(cx)));
 
         LanguageModelRequest {
-            model: CompletionProvider::global(cx).model(),
+            model: CompletionProvider::global(cx).model_for_worktree(self.worktree_id),
             messages: messages.collect(),
             stop: vec![],
-            temperature: 1.0,
+            temperature: None,
+            prefer_device: None,
+            tools: Vec::new(),
+            response_format: None,
+            model_options: self.model_options.clone(),
+            worktree_id: self.worktree_id,
         }
     }
 
@@ -1507,12 +1540,19 @@ impl Context {
                 .chain(Some(LanguageModelRequestMessage {
                     role: Role::User,
                     content: "Summarize the context into a short title without punctuation.".into(),
+                    images: Vec::new(),
+                    tool_name: None,
                 }));
             let request = LanguageModelRequest {
-                model: CompletionProvider::global(cx).model(),
+                model: CompletionProvider::global(cx).model_for_worktree(self.worktree_id),
                 messages: messages.collect(),
                 stop: vec![],
-                temperature: 1.0,
+                temperature: None,
+                prefer_device: None,
+                tools: Vec::new(),
+                response_format: None,
+                model_options: Default::default(),
+                worktree_id: self.worktree_id,
             };
 
             let stream = CompletionProvider::global(cx).complete(request, cx);
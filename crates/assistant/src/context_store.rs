@@ -1,6 +1,6 @@
 use crate::{
-    Context, ContextEvent, ContextId, ContextOperation, ContextVersion, SavedContext,
-    SavedContextMetadata,
+    settings_worktree_id, Context, ContextEvent, ContextId, ContextOperation, ContextVersion,
+    SavedContext, SavedContextMetadata,
 };
 use anyhow::{anyhow, Context as _, Result};
 use client::{proto, telemetry::Telemetry, Client, TypedEnvelope};
@@ -270,6 +270,14 @@ impl ContextStore {
             project::Event::HostReshared | project::Event::Rejoined => {
                 self.synchronize_contexts(cx);
             }
+            project::Event::WorktreeAdded | project::Event::WorktreeRemoved(_) => {
+                let worktree_id = settings_worktree_id(self.project.read(cx), cx);
+                for context in &self.contexts {
+                    if let Some(context) = context.upgrade() {
+                        context.update(cx, |context, _| context.set_worktree_id(worktree_id));
+                    }
+                }
+            }
             project::Event::DisconnectedFromHost => {
                 self.contexts.retain_mut(|context| {
                     if let Some(strong_context) = context.upgrade() {
@@ -425,6 +433,8 @@ impl ContextStore {
             ContextHandle::Weak(context.downgrade())
         };
         self.contexts.push(handle);
+        let worktree_id = settings_worktree_id(self.project.read(cx), cx);
+        context.update(cx, |context, _| context.set_worktree_id(worktree_id));
         self.advertise_contexts(cx);
         cx.subscribe(context, Self::handle_context_event).detach();
     }
@@ -1,7 +1,7 @@
 use crate::{
     assistant_settings::AssistantSettings, humanize_token_count, prompts::generate_content_prompt,
-    AssistantPanel, AssistantPanelEvent, CompletionProvider, Hunk, LanguageModelRequest,
-    LanguageModelRequestMessage, Role, StreamingDiff,
+    settings_worktree_id, AssistantPanel, AssistantPanelEvent, CompletionProvider, Hunk,
+    LanguageModelRequest, LanguageModelRequestMessage, Role, StreamingDiff,
 };
 use anyhow::{anyhow, Context as _, Result};
 use client::telemetry::Telemetry;
@@ -745,7 +745,7 @@ impl InlineAssistant {
         cx: &mut WindowContext,
     ) -> Task<Result<LanguageModelRequest>> {
         cx.spawn(|mut cx| async move {
-            let (user_prompt, context_request, project_name, buffer, range, model) = cx
+            let (user_prompt, context_request, project_name, buffer, range, worktree_id) = cx
                 .read_global(|this: &InlineAssistant, cx: &WindowContext| {
                     let assist = this.assists.get(&assist_id).context("invalid assist")?;
                     let decorations = assist.decorations.as_ref().context("invalid assist")?;
@@ -780,14 +780,16 @@ impl InlineAssistant {
                     });
                     let buffer = editor.read(cx).buffer().read(cx).snapshot(cx);
                     let range = assist.codegen.read(cx).range.clone();
-                    let model = CompletionProvider::global(cx).model();
+                    let worktree_id = assist.workspace.as_ref().and_then(|workspace| {
+                        settings_worktree_id(workspace.upgrade()?.read(cx).project().read(cx), cx)
+                    });
                     anyhow::Ok((
                         user_prompt,
                         context_request,
                         project_name,
                         buffer,
                         range,
-                        model,
+                        worktree_id,
                     ))
                 })??;
 
@@ -803,16 +805,11 @@ impl InlineAssistant {
             };
 
             // Higher Temperature increases the randomness of model outputs.
-            // If Markdown or No Language is Known, increase the randomness for more creative output
+            // If Markdown or No Language is Known, keep the model's temperature for more creative output
             // If Code, decrease temperature to get more deterministic outputs
-            let temperature = if let Some(language) = language_name.clone() {
-                if language.as_ref() == "Markdown" {
-                    1.0
-                } else {
-                    0.5
-                }
-            } else {
-                1.0
+            let temperature = match language_name.as_deref() {
+                Some(language) if language != "Markdown" => Some(0.5),
+                _ => None,
             };
 
             let prompt = cx
@@ -844,13 +841,23 @@ impl InlineAssistant {
             messages.push(LanguageModelRequestMessage {
                 role: Role::User,
                 content: prompt,
+                images: Vec::new(),
+                tool_name: None,
             });
 
+            let model = cx.read_global(|provider: &CompletionProvider, _| {
+                provider.model_for_worktree(worktree_id)
+            })?;
             Ok(LanguageModelRequest {
                 model,
                 messages,
                 stop: vec!["|END|>".to_string()],
                 temperature,
+                prefer_device: None,
+                tools: Vec::new(),
+                response_format: None,
+                model_options: Default::default(),
+                worktree_id,
             })
         })
     }
@@ -1182,7 +1189,7 @@ struct PromptEditor {
     _codegen_subscription: Subscription,
     editor_subscriptions: Vec<Subscription>,
     pending_token_count: Task<Result<()>>,
-    token_count: Option<usize>,
+    remaining_tokens: Option<isize>,
     _token_count_subscriptions: Vec<Subscription>,
     workspace: Option<WeakView<Workspace>>,
 }
@@ -1439,7 +1446,7 @@ impl PromptEditor {
             codegen,
             fs,
             pending_token_count: Task::ready(Ok(())),
-            token_count: None,
+            remaining_tokens: None,
             _token_count_subscriptions: token_count_subscriptions,
             workspace,
         };
@@ -1534,11 +1541,11 @@ impl PromptEditor {
                 })?
                 .await?;
 
-            let token_count = cx
-                .update(|cx| CompletionProvider::global(cx).count_tokens(request, cx))?
+            let remaining_tokens = cx
+                .update(|cx| CompletionProvider::global(cx).remaining_tokens(request, cx))?
                 .await?;
             this.update(&mut cx, |this, cx| {
-                this.token_count = Some(token_count);
+                this.remaining_tokens = Some(remaining_tokens);
                 cx.notify();
             })
         })
@@ -1664,10 +1671,10 @@ impl PromptEditor {
 
     fn render_token_count(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
         let model = CompletionProvider::global(cx).model();
-        let token_count = self.token_count?;
+        let remaining_tokens = self.remaining_tokens?;
         let max_token_count = model.max_token_count();
+        let token_count = max_token_count.saturating_add_signed(-remaining_tokens);
 
-        let remaining_tokens = max_token_count as isize - token_count as isize;
         let token_count_color = if remaining_tokens <= 0 {
             Color::Error
         } else if token_count as f32 / max_token_count as f32 >= 0.8 {
@@ -27,7 +27,19 @@ impl RenderOnce for ModelSelector {
                         menu = menu.custom_entry(
                             {
                                 let model = model.clone();
-                                move |_| Label::new(model.display_name()).into_any_element()
+                                move |_| {
+                                    h_flex()
+                                        .w_full()
+                                        .justify_between()
+                                        .gap_4()
+                                        .child(Label::new(model.display_name()))
+                                        .children(model.description().map(|description| {
+                                            Label::new(description)
+                                                .size(LabelSize::Small)
+                                                .color(Color::Muted)
+                                        }))
+                                        .into_any_element()
+                                }
                             },
                             {
                                 let fs = self.fs.clone();
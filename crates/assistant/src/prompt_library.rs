@@ -741,9 +741,16 @@ impl PromptLibrary {
                                     messages: vec![LanguageModelRequestMessage {
                                         role: Role::System,
                                         content: body.to_string(),
+                                        images: Vec::new(),
+                                        tool_name: None,
                                     }],
                                     stop: Vec::new(),
-                                    temperature: 1.,
+                                    temperature: None,
+                                    prefer_device: None,
+                                    tools: Vec::new(),
+                                    response_format: None,
+                                    model_options: Default::default(),
+                                    worktree_id: None,
                                 },
                                 cx,
                             )
@@ -1,7 +1,8 @@
 use crate::{
     assistant_settings::AssistantSettings, humanize_token_count,
-    prompts::generate_terminal_assistant_prompt, AssistantPanel, AssistantPanelEvent,
-    CompletionProvider, LanguageModelRequest, LanguageModelRequestMessage, Role,
+    prompts::generate_terminal_assistant_prompt, settings_worktree_id, AssistantPanel,
+    AssistantPanelEvent, CompletionProvider, LanguageModelRequest, LanguageModelRequestMessage,
+    Role,
 };
 use anyhow::{Context as _, Result};
 use client::telemetry::Telemetry;
@@ -212,7 +213,10 @@ impl TerminalInlineAssistant {
     ) -> Result<LanguageModelRequest> {
         let assist = self.assists.get(&assist_id).context("invalid assist")?;
 
-        let model = CompletionProvider::global(cx).model();
+        let worktree_id = assist.workspace.as_ref().and_then(|workspace| {
+            settings_worktree_id(workspace.upgrade()?.read(cx).project().read(cx), cx)
+        });
+        let model = CompletionProvider::global(cx).model_for_worktree(worktree_id);
 
         let shell = std::env::var("SHELL").ok();
         let working_directory = assist
@@ -262,13 +266,20 @@ impl TerminalInlineAssistant {
         messages.push(LanguageModelRequestMessage {
             role: Role::User,
             content: prompt,
+            images: Vec::new(),
+            tool_name: None,
         });
 
         Ok(LanguageModelRequest {
             model,
             messages,
             stop: Vec::new(),
-            temperature: 1.0,
+            temperature: None,
+            prefer_device: None,
+            tools: Vec::new(),
+            response_format: None,
+            model_options: Default::default(),
+            worktree_id,
         })
     }
 
@@ -439,7 +450,7 @@ struct PromptEditor {
     _codegen_subscription: Subscription,
     editor_subscriptions: Vec<Subscription>,
     pending_token_count: Task<Result<()>>,
-    token_count: Option<usize>,
+    remaining_tokens: Option<isize>,
     _token_count_subscriptions: Vec<Subscription>,
     workspace: Option<WeakView<Workspace>>,
 }
@@ -688,7 +699,7 @@ impl PromptEditor {
             codegen,
             fs,
             pending_token_count: Task::ready(Ok(())),
-            token_count: None,
+            remaining_tokens: None,
             _token_count_subscriptions: token_count_subscriptions,
             workspace,
         };
@@ -745,11 +756,11 @@ impl PromptEditor {
                     inline_assistant.request_for_inline_assist(assist_id, cx)
                 })??;
 
-            let token_count = cx
-                .update(|cx| CompletionProvider::global(cx).count_tokens(request, cx))?
+            let remaining_tokens = cx
+                .update(|cx| CompletionProvider::global(cx).remaining_tokens(request, cx))?
                 .await?;
             this.update(&mut cx, |this, cx| {
-                this.token_count = Some(token_count);
+                this.remaining_tokens = Some(remaining_tokens);
                 cx.notify();
             })
         })
@@ -877,10 +888,10 @@ impl PromptEditor {
 
     fn render_token_count(&self, cx: &mut ViewContext<Self>) -> Option<impl IntoElement> {
         let model = CompletionProvider::global(cx).model();
-        let token_count = self.token_count?;
+        let remaining_tokens = self.remaining_tokens?;
         let max_token_count = model.max_token_count();
+        let token_count = max_token_count.saturating_add_signed(-remaining_tokens);
 
-        let remaining_tokens = max_token_count as isize - token_count as isize;
         let token_count_color = if remaining_tokens <= 0 {
             Color::Error
         } else if token_count as f32 / max_token_count as f32 >= 0.8 {
@@ -23,6 +23,7 @@ pub struct Keymap {
     binding_indices_by_action_id: HashMap<TypeId, SmallVec<[usize; 3]>>,
     disabled_keystrokes:
         HashMap<SmallVec<[Keystroke; 2]>, HashSet<Option<KeyBindingContextPredicate>>>,
+    strict_function_modifier: bool,
     version: KeymapVersion,
 }
 
@@ -62,6 +63,20 @@ impl Keymap {
         self.version.0 += 1;
     }
 
+    /// Whether navigation keys reported with the `fn` modifier only match bindings
+    /// that include `fn`. By default they also match bindings without it, see
+    /// [`Keystroke::without_navigation_function_modifier`].
+    pub fn strict_function_modifier(&self) -> bool {
+        self.strict_function_modifier
+    }
+
+    /// Set whether navigation keys reported with the `fn` modifier only match
+    /// bindings that include `fn`.
+    pub fn set_strict_function_modifier(&mut self, strict: bool) {
+        self.strict_function_modifier = strict;
+        self.version.0 += 1;
+    }
+
     /// Reset this keymap to its initial state.
     pub fn clear(&mut self) {
         self.bindings.clear();
@@ -118,11 +133,29 @@ impl Keymap {
     }
 }
 
+/// Layers `overrides`, such as the user's bindings, over `base` bindings, such as
+/// the defaults. A base binding is dropped when an override is bound to the same
+/// keystroke, compared in its [normalized](Keystroke::normalized) form.
+pub fn merge_keystroke_overrides(
+    base: Vec<(Keystroke, Box<dyn Action>)>,
+    overrides: Vec<(Keystroke, Box<dyn Action>)>,
+) -> Vec<(Keystroke, Box<dyn Action>)> {
+    let overridden = overrides
+        .iter()
+        .map(|(keystroke, _)| keystroke.normalized())
+        .collect::<HashSet<_>>();
+    base.into_iter()
+        .filter(|(keystroke, _)| !overridden.contains(&keystroke.normalized()))
+        .chain(overrides)
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate as gpui;
-    use gpui::actions;
+    use gpui::{actions, KeystrokeRenderStyle};
+    use std::{cell::RefCell, rc::Rc};
 
     actions!(
         keymap_test,
@@ -180,4 +213,70 @@ mod tests {
         // binding is globally disabled
         assert!(!keymap.binding_enabled(&bindings[1], &[KeyContext::parse("barf").unwrap()]));
     }
+
+    #[test]
+    fn test_function_modifier_on_navigation_keys() {
+        let keymap = Rc::new(RefCell::new(Keymap::new(vec![
+            KeyBinding::new("cmd-left", ActionAlpha {}, None),
+            KeyBinding::new("fn-down", ActionBeta {}, None),
+        ])));
+        let mut matcher = KeystrokeMatcher::new(keymap.clone());
+        let mut matched_actions = |keystroke: &str| {
+            matcher
+                .match_keystroke(&Keystroke::parse(keystroke).unwrap(), &[])
+                .bindings
+                .iter()
+                .map(|binding| binding.action().name())
+                .collect::<Vec<_>>()
+        };
+
+        // macOS reports arrow keys with `fn`, which shouldn't get in the way of `cmd-left`.
+        assert_eq!(matched_actions("cmd-fn-left"), ["keymap_test::ActionAlpha"]);
+        assert_eq!(matched_actions("cmd-left"), ["keymap_test::ActionAlpha"]);
+        // Bindings that explicitly include `fn` keep working.
+        assert_eq!(matched_actions("fn-down"), ["keymap_test::ActionBeta"]);
+        // Other keys keep their `fn` modifier.
+        assert!(matched_actions("fn-a").is_empty());
+
+        keymap.borrow_mut().set_strict_function_modifier(true);
+        assert!(matched_actions("cmd-fn-left").is_empty());
+        assert_eq!(matched_actions("fn-down"), ["keymap_test::ActionBeta"]);
+    }
+
+    #[test]
+    fn test_merge_keystroke_overrides() {
+        let binding = |keystroke: &str, action: &dyn Action| {
+            (Keystroke::parse(keystroke).unwrap(), action.boxed_clone())
+        };
+        let base = vec![
+            binding("ctrl-a", &ActionAlpha {}),
+            binding("ctrl-b", &ActionBeta {}),
+            binding("ctrl-left", &ActionAlpha {}),
+        ];
+        let overrides = vec![
+            binding("ctrl-a", &ActionGamma {}),
+            binding("ctrl-fn-left", &ActionDelta {}),
+        ];
+
+        let merged = merge_keystroke_overrides(base, overrides)
+            .into_iter()
+            .map(|(keystroke, action)| {
+                format!(
+                    "{} {}",
+                    keystroke.render(KeystrokeRenderStyle::Ascii),
+                    action.name()
+                )
+            })
+            .collect::<Vec<_>>();
+        // The user's bindings shadow the defaults on the same key, including
+        // `ctrl-left`, which macOS reports as `ctrl-fn-left`.
+        assert_eq!(
+            merged,
+            [
+                "ctrl-b keymap_test::ActionBeta",
+                "ctrl-a keymap_test::ActionGamma",
+                "ctrl-fn-left keymap_test::ActionDelta",
+            ]
+        );
+    }
 }
@@ -57,7 +57,16 @@ impl KeystrokeMatcher {
                 continue;
             }
 
-            for candidate in keystroke.match_candidates() {
+            let mut candidates = keystroke.match_candidates();
+            if !keymap.strict_function_modifier() {
+                let without_function = candidates
+                    .iter()
+                    .filter_map(Keystroke::without_navigation_function_modifier)
+                    .collect::<SmallVec<[Keystroke; 2]>>();
+                candidates.extend(without_function);
+            }
+
+            for candidate in candidates {
                 self.pending_keystrokes.push(candidate.clone());
                 match binding.match_keystrokes(&self.pending_keystrokes) {
                     KeyMatch::Matched => {
@@ -1,7 +1,7 @@
 use anyhow::anyhow;
+use collections::HashMap;
 use serde::Deserialize;
 use smallvec::SmallVec;
-use std::fmt::Write;
 
 /// A keystroke and associated metadata generated by the platform
 #[derive(Clone, Debug, Eq, PartialEq, Default, Deserialize, Hash)]
@@ -58,7 +58,21 @@ impl Keystroke {
     /// [ctrl-][alt-][shift-][cmd-][fn-]key[->ime_key]
     /// ime_key syntax is only used for generating test events,
     /// when matching a key with an ime_key set will be matched without it.
+    ///
+    /// A key whose name collides with a modifier can be written literally
+    /// by prefixing it with `key:`, e.g. `key:shift` is a key named "shift".
     pub fn parse(source: &str) -> anyhow::Result<Self> {
+        Self::parse_with_features(source).map(|(keystroke, _)| keystroke)
+    }
+
+    /// Parses a keystroke like [`Keystroke::parse`], also returning the optional
+    /// parts of the syntax it uses. A keymap loader can warn about bindings that
+    /// use features for which [`KeystrokeSyntaxFeature::requires_newer_grammar`]
+    /// is true, as they won't work everywhere.
+    pub fn parse_with_features(
+        source: &str,
+    ) -> anyhow::Result<(Self, SmallVec<[KeystrokeSyntaxFeature; 2]>)> {
+        let mut features = SmallVec::new();
         let mut control = false;
         let mut alt = false;
         let mut shift = false;
@@ -74,8 +88,22 @@ impl Keystroke {
                 "alt" => alt = true,
                 "shift" => shift = true,
                 "fn" => function = true,
-                "cmd" | "super" | "win" => platform = true,
+                "cmd" => platform = true,
+                "super" | "win" => {
+                    platform = true;
+                    push_feature(&mut features, KeystrokeSyntaxFeature::PlatformAlias);
+                }
                 _ => {
+                    let component = match component
+                        .strip_prefix("key:")
+                        .filter(|literal| !literal.is_empty())
+                    {
+                        Some(literal) => {
+                            push_feature(&mut features, KeystrokeSyntaxFeature::LiteralKey);
+                            literal
+                        }
+                        None => component,
+                    };
                     if let Some(next) = components.peek() {
                         if next.is_empty() && source.ends_with('-') {
                             key = Some(String::from("-"));
@@ -117,7 +145,7 @@ impl Keystroke {
 
         let key = key.ok_or_else(|| anyhow!("Invalid keystroke `{}`", source))?;
 
-        Ok(Keystroke {
+        let keystroke = Keystroke {
             modifiers: Modifiers {
                 control,
                 alt,
@@ -127,7 +155,28 @@ impl Keystroke {
             },
             key,
             ime_key,
-        })
+        };
+        Ok((keystroke, features))
+    }
+
+    /// Parses a keystroke like [`Keystroke::parse`], first expanding the modifiers
+    /// defined in `aliases`, so that `hyper-x` can stand for `ctrl-alt-shift-cmd-x`.
+    pub fn parse_with_aliases(source: &str, aliases: &KeystrokeAliasTable) -> anyhow::Result<Self> {
+        Self::parse(&aliases.expand(source))
+    }
+
+    /// Parses a keystroke like [`Keystroke::parse`], also accepting the legacy forms
+    /// found in old keymaps: `+` between the parts, as in `ctrl+a`, and the modifier
+    /// names `control`, `option`, `opt` and `command`. Logs a warning suggesting the
+    /// current form when a legacy form is used.
+    pub fn parse_lenient(source: &str) -> anyhow::Result<Self> {
+        let modern = modernize_legacy_keystroke(source);
+        if modern != source {
+            log::warn!(
+                "keystroke `{source}` uses a deprecated syntax, write it as `{modern}` instead"
+            );
+        }
+        Self::parse(&modern)
     }
 
     /// Returns true if this keystroke left
@@ -141,6 +190,303 @@ impl Keystroke {
                 || self.modifiers.alt)
     }
 
+    /// Returns true if this keystroke's key is a symbol that a US layout types directly
+    /// but common international layouts only type with `alt`/AltGr, such as `$` on a
+    /// Czech or Swedish keyboard or `@` on a German one. Such a binding only matches
+    /// through the character the IME reports (see `match_candidates`),
+    /// so it may be unreachable when combined with other modifiers, and a keymap
+    /// linter can warn about it.
+    pub fn is_layout_sensitive(&self) -> bool {
+        matches!(
+            self.key.as_str(),
+            "@" | "#" | "$" | "[" | "]" | "{" | "}" | "\\" | "|" | "~" | "^" | "`"
+        )
+    }
+
+    /// Returns true if this keystroke types text in an editor, so that a command bound
+    /// to it would stop the user from typing that text, as with a binding for `a` or
+    /// `shift-a`. Keystrokes with `ctrl`, `cmd` or `fn` never type text, and neither
+    /// do ones with `alt` except on macOS, where `option` types special characters.
+    pub fn conflicts_with_text_input(&self) -> bool {
+        let modifiers = &self.modifiers;
+        if modifiers.control || modifiers.platform || modifiers.function {
+            return false;
+        }
+        if modifiers.alt && !cfg!(target_os = "macos") {
+            return false;
+        }
+        !self.key.is_empty() && is_printable_key(&self.key)
+    }
+
+    /// Returns false if the key is unlikely to exist on a standard compact keyboard,
+    /// such as a numpad key, a function key above `f12` or a media key, so that a
+    /// keymap linter can warn that many users can't press a binding for it. This is
+    /// a heuristic based on the key's name, not on the connected hardware.
+    pub fn is_commonly_available(&self) -> bool {
+        let key = self.key.as_str();
+        if key.starts_with("kp_") || key.starts_with("numpad") {
+            return false;
+        }
+        if let Some(number) = key.strip_prefix('f').and_then(|n| n.parse::<u32>().ok()) {
+            return number <= 12;
+        }
+        !matches!(
+            key,
+            "numlock"
+                | "scrolllock"
+                | "pause"
+                | "printscreen"
+                | "play"
+                | "playpause"
+                | "stop"
+                | "next"
+                | "previous"
+                | "mute"
+                | "volumeup"
+                | "volumedown"
+        )
+    }
+
+    /// Returns the key as printed on the physical key that was pressed, before the
+    /// IME transformed it, for bindings that target the key regardless of what it
+    /// types. This is always `key`; the text the key inserted is `ime_key`.
+    ///
+    /// For example on a US layout on macOS `option-s` types `ß`: the base key is
+    /// `s` and the ime_key is `ß`. Likewise on a German layout `shift-7` types `/`,
+    /// and its base key is `7`.
+    pub fn base_key(&self) -> &str {
+        &self.key
+    }
+
+    /// Returns true if this keystroke is equal to `other` when treating `cmd` and
+    /// `ctrl` interchangeably as the platform's primary modifier, so that `cmd-c`
+    /// written for macOS matches `ctrl-c` written for Linux or Windows.
+    pub fn matches_primary(&self, other: &Keystroke) -> bool {
+        fn normalize(modifiers: Modifiers) -> (Modifiers, bool) {
+            if modifiers.platform != modifiers.control {
+                let modifiers = Modifiers {
+                    platform: false,
+                    control: false,
+                    ..modifiers
+                };
+                (modifiers, true)
+            } else {
+                (modifiers, false)
+            }
+        }
+
+        self.key == other.key && normalize(self.modifiers) == normalize(other.modifiers)
+    }
+
+    /// macOS reports arrow keys and the other navigation keys with the `fn` modifier set,
+    /// as they live on the function layer of compact keyboards. For those keys this
+    /// returns the keystroke without `fn`, so that a binding like `cmd-left` matches.
+    pub fn without_navigation_function_modifier(&self) -> Option<Keystroke> {
+        let is_navigation_key = matches!(
+            self.key.as_str(),
+            "left" | "right" | "up" | "down" | "home" | "end" | "pageup" | "pagedown" | "delete"
+        );
+        if self.modifiers.function && is_navigation_key {
+            let mut keystroke = self.clone();
+            keystroke.modifiers.function = false;
+            Some(keystroke)
+        } else {
+            None
+        }
+    }
+
+    /// Returns this keystroke in the form it is matched against bindings in, without
+    /// the `ime_key` and without `fn` on navigation keys, so that two keystrokes
+    /// that trigger the same bindings compare equal.
+    pub fn normalized(&self) -> Keystroke {
+        let keystroke = self
+            .without_navigation_function_modifier()
+            .unwrap_or_else(|| self.clone());
+        Keystroke {
+            ime_key: None,
+            ..keystroke
+        }
+    }
+
+    /// Returns this keystroke with a shifted symbol written as the key it is typed
+    /// with plus `shift`, so that `!` and `shift-1` compare equal. Keystrokes are
+    /// only canonicalized when a caller opts in by calling this.
+    ///
+    /// The table of symbols is that of a US layout, so this is wrong for layouts
+    /// that put the symbols on other keys: `!` is not `shift-1` on a French or
+    /// German keyboard, for example.
+    pub fn with_us_shifted_symbol_canonicalized(&self) -> Keystroke {
+        let base_key = match self.key.as_str() {
+            "!" => "1",
+            "@" => "2",
+            "#" => "3",
+            "$" => "4",
+            "%" => "5",
+            "^" => "6",
+            "&" => "7",
+            "*" => "8",
+            "(" => "9",
+            ")" => "0",
+            "_" => "-",
+            "+" => "=",
+            "{" => "[",
+            "}" => "]",
+            "|" => "\\",
+            ":" => ";",
+            "\"" => "'",
+            "<" => ",",
+            ">" => ".",
+            "?" => "/",
+            "~" => "`",
+            _ => return self.clone(),
+        };
+        Keystroke {
+            modifiers: Modifiers {
+                shift: true,
+                ..self.modifiers
+            },
+            key: base_key.to_string(),
+            ime_key: self.ime_key.clone(),
+        }
+    }
+
+    /// Returns the path of the icon drawn on a keycap for this keystroke's key, for
+    /// keys that are shown as a symbol rather than as text, such as the arrow keys
+    /// or `shift` bound on its own. Returns `None` for keys shown as text.
+    pub fn key_icon_name(&self) -> Option<&'static str> {
+        match self.key.as_str() {
+            "left" => Some("icons/arrow_left.svg"),
+            "right" => Some("icons/arrow_right.svg"),
+            "up" => Some("icons/arrow_up.svg"),
+            "down" => Some("icons/arrow_down.svg"),
+            "backspace" => Some("icons/backspace.svg"),
+            "delete" => Some("icons/delete.svg"),
+            "return" | "enter" => Some("icons/return.svg"),
+            "tab" => Some("icons/tab.svg"),
+            "space" => Some("icons/space.svg"),
+            "escape" => Some("icons/escape.svg"),
+            "pagedown" => Some("icons/page_down.svg"),
+            "pageup" => Some("icons/page_up.svg"),
+            "shift" => Some("icons/shift.svg"),
+            "control" => Some("icons/control.svg"),
+            "platform" => Some("icons/command.svg"),
+            "alt" => Some("icons/option.svg"),
+            _ => None,
+        }
+    }
+
+    /// Returns the name of the shortcut of the operating system that this keystroke
+    /// collides with on the given platform, if any. The operating system handles
+    /// these keystrokes itself, so bindings for them never reach the application.
+    pub fn shadows_system_shortcut(&self, platform: KeystrokePlatform) -> Option<&'static str> {
+        let shortcuts: &[(&str, &str)] = match platform {
+            KeystrokePlatform::Mac => &[
+                ("cmd-space", "Spotlight"),
+                ("cmd-alt-space", "Finder search"),
+                ("ctrl-space", "Select the previous input source"),
+                ("cmd-tab", "Application switcher"),
+                ("cmd-shift-tab", "Application switcher"),
+                ("cmd-shift-3", "Screenshot"),
+                ("cmd-shift-4", "Screenshot of a selection"),
+                ("cmd-shift-5", "Screenshot and recording options"),
+                ("cmd-alt-escape", "Force Quit"),
+                ("cmd-ctrl-q", "Lock Screen"),
+                ("ctrl-up", "Mission Control"),
+                ("ctrl-down", "Application windows"),
+                ("ctrl-left", "Move left a space"),
+                ("ctrl-right", "Move right a space"),
+            ],
+            KeystrokePlatform::Linux => &[
+                ("super", "Activities overview"),
+                ("super-space", "Switch input source"),
+                ("super-l", "Lock screen"),
+                ("alt-tab", "Switch applications"),
+                ("alt-shift-tab", "Switch applications"),
+                ("alt-f2", "Run a command"),
+                ("alt-f4", "Close window"),
+                ("ctrl-alt-delete", "Log out"),
+                ("ctrl-alt-t", "Open a terminal"),
+            ],
+            KeystrokePlatform::Windows => &[
+                ("alt-tab", "Switch windows"),
+                ("alt-shift-tab", "Switch windows"),
+                ("alt-f4", "Close window"),
+                ("ctrl-alt-delete", "Security options"),
+                ("ctrl-shift-escape", "Task Manager"),
+                ("win-d", "Show desktop"),
+                ("win-e", "File Explorer"),
+                ("win-l", "Lock"),
+                ("win-r", "Run"),
+                ("win-tab", "Task View"),
+            ],
+        };
+
+        let keystroke = self.normalized();
+        shortcuts
+            .iter()
+            .find(|(source, _)| Keystroke::parse(source).ok().as_ref() == Some(&keystroke))
+            .map(|(_, name)| *name)
+    }
+
+    /// Returns true if this is the conventional shortcut for the given standard
+    /// action on the given platform, such as `cmd-w` for closing on macOS, so that
+    /// native menus can show it and custom keymaps can be checked against it.
+    pub fn is_standard_action_shortcut(
+        &self,
+        action: StandardAction,
+        platform: KeystrokePlatform,
+    ) -> bool {
+        let keystroke = self.normalized();
+        action
+            .shortcuts(platform)
+            .iter()
+            .any(|source| Keystroke::parse(source).ok().as_ref() == Some(&keystroke))
+    }
+
+    /// Returns true if this is the platform's paste shortcut: `cmd-v` on macOS,
+    /// `ctrl-v` elsewhere, and also `shift-insert` on Linux.
+    pub fn is_paste(&self) -> bool {
+        self.is_primary_shortcut("v") || self.is_linux_insert_shortcut("shift-insert")
+    }
+
+    /// Returns true if this is the platform's copy shortcut: `cmd-c` on macOS,
+    /// `ctrl-c` elsewhere, and also `ctrl-insert` on Linux.
+    pub fn is_copy(&self) -> bool {
+        self.is_primary_shortcut("c") || self.is_linux_insert_shortcut("ctrl-insert")
+    }
+
+    /// Returns true if this is the platform's cut shortcut: `cmd-x` on macOS,
+    /// `ctrl-x` elsewhere, and also `shift-delete` on Linux.
+    pub fn is_cut(&self) -> bool {
+        self.is_primary_shortcut("x") || self.is_linux_insert_shortcut("shift-delete")
+    }
+
+    fn is_primary_shortcut(&self, key: &str) -> bool {
+        let primary = if cfg!(target_os = "macos") {
+            Modifiers::command()
+        } else {
+            Modifiers::control()
+        };
+        self.modifiers == primary && self.key == key
+    }
+
+    fn is_linux_insert_shortcut(&self, source: &str) -> bool {
+        cfg!(target_os = "linux")
+            && Keystroke::parse(source).map_or(false, |shortcut| {
+                self.modifiers == shortcut.modifiers && self.key == shortcut.key
+            })
+    }
+
+    /// Returns true if this keystroke is a dead key, that is, one which produces no
+    /// text on its own but combines with the following keystroke. For example on
+    /// many European layouts typing `´` and then `e` inserts `é`.
+    pub fn is_dead_key(&self) -> bool {
+        match self.ime_key.as_deref() {
+            Some(ime_key) => ime_key.is_empty() || ime_key.chars().all(is_combining_mark),
+            None => false,
+        }
+    }
+
     /// Returns a new keystroke with the ime_key filled.
     /// This is used for dispatch_keystroke where we want users to
     /// be able to simulate typing "space", etc.
@@ -167,37 +513,340 @@ impl Keystroke {
         }
         self
     }
+
+    /// Parses a keystroke written in binding syntax and fills in the `ime_key` the
+    /// platform would report for it, for synthesizing realistic key events in tests.
+    pub fn for_dispatch(source: &str) -> anyhow::Result<Self> {
+        Ok(Self::parse(source)?.with_simulated_ime())
+    }
+}
+
+fn modernize_legacy_keystroke(source: &str) -> String {
+    let uses_plus_separator = !source.contains('-')
+        && source.contains('+')
+        && source.split('+').all(|component| !component.is_empty());
+    let source = if uses_plus_separator {
+        source.replace('+', "-")
+    } else {
+        source.to_string()
+    };
+
+    let mut legacy_modifiers = KeystrokeAliasTable::default();
+    for (legacy, modern) in [
+        ("control", "ctrl"),
+        ("option", "alt"),
+        ("opt", "alt"),
+        ("command", "cmd"),
+    ] {
+        legacy_modifiers.insert(legacy, modern);
+    }
+    legacy_modifiers.expand(&source)
+}
+
+/// The names of the keys that don't type a character of their own, as reported by
+/// the platform and written in bindings, e.g. `enter`, `f13`, `kp_enter` or
+/// `volumeup`. Every other key is named by the character printed on it.
+pub fn special_key_names() -> &'static [&'static str] {
+    &[
+        "space",
+        "tab",
+        "enter",
+        "backspace",
+        "delete",
+        "insert",
+        "escape",
+        "up",
+        "down",
+        "left",
+        "right",
+        "home",
+        "end",
+        "pageup",
+        "pagedown",
+        "f1",
+        "f2",
+        "f3",
+        "f4",
+        "f5",
+        "f6",
+        "f7",
+        "f8",
+        "f9",
+        "f10",
+        "f11",
+        "f12",
+        "f13",
+        "f14",
+        "f15",
+        "f16",
+        "f17",
+        "f18",
+        "f19",
+        "f20",
+        "f21",
+        "f22",
+        "f23",
+        "f24",
+        "kp_0",
+        "kp_1",
+        "kp_2",
+        "kp_3",
+        "kp_4",
+        "kp_5",
+        "kp_6",
+        "kp_7",
+        "kp_8",
+        "kp_9",
+        "kp_decimal",
+        "kp_divide",
+        "kp_multiply",
+        "kp_subtract",
+        "kp_add",
+        "kp_enter",
+        "kp_equal",
+        "numlock",
+        "scrolllock",
+        "pause",
+        "printscreen",
+        "play",
+        "playpause",
+        "stop",
+        "next",
+        "previous",
+        "mute",
+        "volumeup",
+        "volumedown",
+    ]
+}
+
+/// Returns true if `partial` is the start of the longer sequence `binding`, so that
+/// a dispatcher that received `partial` should wait for further keystrokes. The
+/// keystrokes are compared [`normalized`](Keystroke::normalized), so the text the
+/// IME inserted for them is ignored.
+pub fn is_prefix_of(partial: &[Keystroke], binding: &[Keystroke]) -> bool {
+    partial.len() < binding.len()
+        && partial
+            .iter()
+            .zip(binding)
+            .all(|(partial, binding)| partial.normalized() == binding.normalized())
+}
+
+/// Formats a hint for every binding in `candidates` that continues the pressed
+/// `prefix`, such as `ctrl-k → ctrl-s`, for which-key style popups that list the
+/// keys to press next. Candidates that don't start with `prefix` are skipped.
+pub fn format_continuations(
+    prefix: &[Keystroke],
+    candidates: &[Vec<Keystroke>],
+    style: KeystrokeRenderStyle,
+) -> Vec<String> {
+    let render = |keystrokes: &[Keystroke]| {
+        keystrokes
+            .iter()
+            .map(|keystroke| keystroke.render(style))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    let prefix_text = render(prefix);
+    candidates
+        .iter()
+        .filter(|candidate| is_prefix_of(prefix, candidate))
+        .map(|candidate| format!("{prefix_text} → {}", render(&candidate[prefix.len()..])))
+        .collect()
 }
 
+/// The keys of keystrokes in which only a modifier was pressed, as parsed from
+/// `shift`, `ctrl`, `alt`, `cmd` and `fn` on their own.
+const MODIFIER_KEY_NAMES: &[&str] = &["shift", "control", "alt", "platform", "function"];
+
+/// Returns true if the key types text, either its own name or, for `space`,
+/// `tab` and `enter`, the whitespace it stands for.
 fn is_printable_key(key: &str) -> bool {
-    match key {
-        "up" | "down" | "left" | "right" | "pageup" | "pagedown" | "home" | "end" | "delete"
-        | "escape" | "backspace" | "f1" | "f2" | "f3" | "f4" | "f5" | "f6" | "f7" | "f8" | "f9"
-        | "f10" | "f11" | "f12" => false,
-        _ => true,
+    matches!(key, "space" | "tab" | "enter")
+        || !(special_key_names().contains(&key) || MODIFIER_KEY_NAMES.contains(&key))
+}
+
+fn is_combining_mark(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+    )
+}
+
+/// An optional part of the keystroke syntax, as reported by
+/// [`Keystroke::parse_with_features`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
+pub enum KeystrokeSyntaxFeature {
+    /// `super-` or `win-` written in place of `cmd-`. Every version of the
+    /// grammar accepts these, so this is informational only.
+    PlatformAlias,
+    /// A key written literally with the `key:` prefix, such as `key:shift`.
+    LiteralKey,
+}
+
+impl KeystrokeSyntaxFeature {
+    /// Whether older versions of the grammar fail to parse this feature.
+    pub fn requires_newer_grammar(&self) -> bool {
+        match self {
+            Self::PlatformAlias => false,
+            Self::LiteralKey => true,
+        }
     }
 }
 
-impl std::fmt::Display for Keystroke {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+fn push_feature(
+    features: &mut SmallVec<[KeystrokeSyntaxFeature; 2]>,
+    feature: KeystrokeSyntaxFeature,
+) {
+    if !features.contains(&feature) {
+        features.push(feature);
+    }
+}
+
+/// User-defined modifier shorthands, such as `hyper` for `ctrl-alt-shift-cmd`,
+/// used by [`Keystroke::parse_with_aliases`].
+#[derive(Clone, Debug, Default)]
+pub struct KeystrokeAliasTable {
+    aliases: HashMap<String, String>,
+}
+
+impl KeystrokeAliasTable {
+    /// Defines `alias` as a modifier that expands to the given modifiers,
+    /// written in keystroke syntax without a key, e.g. `ctrl-alt-shift-cmd`.
+    pub fn insert(&mut self, alias: impl Into<String>, expansion: impl Into<String>) {
+        self.aliases.insert(alias.into(), expansion.into());
+    }
+
+    /// Replaces the aliased modifiers in `source` with their expansions. The key
+    /// itself is never expanded, so an alias can still be bound on its own.
+    fn expand(&self, source: &str) -> String {
+        let mut components = source.split('-').peekable();
+        let mut expanded = Vec::new();
+        while let Some(component) = components.next() {
+            let is_modifier = components
+                .peek()
+                .map_or(false, |next| !next.starts_with('>'));
+            match self.aliases.get(component) {
+                Some(expansion) if is_modifier => expanded.push(expansion.as_str()),
+                _ => expanded.push(component),
+            }
+        }
+        expanded.join("-")
+    }
+}
+
+/// An operating system whose shortcuts are checked by
+/// [`Keystroke::shadows_system_shortcut`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeystrokePlatform {
+    Mac,
+    Linux,
+    Windows,
+}
+
+impl KeystrokePlatform {
+    /// Returns the platform the application is running on.
+    pub const fn current() -> Self {
+        if cfg!(target_os = "macos") {
+            Self::Mac
+        } else if cfg!(target_os = "windows") {
+            Self::Windows
+        } else {
+            Self::Linux
+        }
+    }
+}
+
+/// An action that every platform has a conventional shortcut for, as checked by
+/// [`Keystroke::is_standard_action_shortcut`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StandardAction {
+    NewWindow,
+    Open,
+    Save,
+    Close,
+    Quit,
+    Undo,
+    Redo,
+    Find,
+    SelectAll,
+    Minimize,
+}
+
+impl StandardAction {
+    fn shortcuts(self, platform: KeystrokePlatform) -> &'static [&'static str] {
+        match (self, platform) {
+            (Self::NewWindow, KeystrokePlatform::Mac) => &["cmd-n"],
+            (Self::NewWindow, _) => &["ctrl-n"],
+            (Self::Open, KeystrokePlatform::Mac) => &["cmd-o"],
+            (Self::Open, _) => &["ctrl-o"],
+            (Self::Save, KeystrokePlatform::Mac) => &["cmd-s"],
+            (Self::Save, _) => &["ctrl-s"],
+            (Self::Close, KeystrokePlatform::Mac) => &["cmd-w"],
+            (Self::Close, _) => &["ctrl-w"],
+            (Self::Quit, KeystrokePlatform::Mac) => &["cmd-q"],
+            (Self::Quit, KeystrokePlatform::Linux) => &["ctrl-q"],
+            (Self::Quit, KeystrokePlatform::Windows) => &["alt-f4"],
+            (Self::Undo, KeystrokePlatform::Mac) => &["cmd-z"],
+            (Self::Undo, _) => &["ctrl-z"],
+            (Self::Redo, KeystrokePlatform::Mac) => &["cmd-shift-z"],
+            (Self::Redo, KeystrokePlatform::Linux) => &["ctrl-shift-z"],
+            (Self::Redo, KeystrokePlatform::Windows) => &["ctrl-y", "ctrl-shift-z"],
+            (Self::Find, KeystrokePlatform::Mac) => &["cmd-f"],
+            (Self::Find, _) => &["ctrl-f"],
+            (Self::SelectAll, KeystrokePlatform::Mac) => &["cmd-a"],
+            (Self::SelectAll, _) => &["ctrl-a"],
+            (Self::Minimize, KeystrokePlatform::Mac) => &["cmd-m"],
+            (Self::Minimize, _) => &[],
+        }
+    }
+}
+
+/// How a keystroke is rendered as text by [`Keystroke::render`].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum KeystrokeRenderStyle {
+    /// Modifier and key symbols, such as `⌘⇧A`, as shown in menus.
+    Glyph,
+    /// The keymap syntax, such as `cmd-shift-a`, for compact inline hints.
+    Ascii,
+    /// Spelled-out names, such as `Command Shift A`, for screen readers.
+    Verbose,
+}
+
+impl Keystroke {
+    /// Renders this keystroke as text in the given style.
+    pub fn render(&self, style: KeystrokeRenderStyle) -> String {
+        match style {
+            KeystrokeRenderStyle::Glyph => self.render_glyphs(),
+            KeystrokeRenderStyle::Ascii => self.render_ascii(),
+            KeystrokeRenderStyle::Verbose => self.render_verbose(),
+        }
+    }
+
+    fn render_glyphs(&self) -> String {
+        self.render_glyphs_for(KeystrokePlatform::current())
+    }
+
+    fn render_glyphs_for(&self, platform: KeystrokePlatform) -> String {
+        let mut text = String::new();
         if self.modifiers.control {
-            f.write_char('^')?;
+            text.push('^');
         }
         if self.modifiers.alt {
-            f.write_char('⌥')?;
+            text.push('⌥');
         }
         if self.modifiers.platform {
-            #[cfg(target_os = "macos")]
-            f.write_char('⌘')?;
-
-            #[cfg(target_os = "linux")]
-            f.write_char('❖')?;
-
-            #[cfg(target_os = "windows")]
-            f.write_char('⊞')?;
+            text.push(match platform {
+                KeystrokePlatform::Mac => '⌘',
+                KeystrokePlatform::Linux => '❖',
+                KeystrokePlatform::Windows => '⊞',
+            });
         }
         if self.modifiers.shift {
-            f.write_char('⇧')?;
+            text.push('⇧');
         }
         let key = match self.key.as_str() {
             "backspace" => '⌫',
@@ -215,11 +864,238 @@ impl std::fmt::Display for Keystroke {
                 if key.len() == 1 {
                     key.chars().next().unwrap().to_ascii_uppercase()
                 } else {
-                    return f.write_str(key);
+                    text.push_str(key);
+                    return text;
+                }
+            }
+        };
+        text.push(key);
+        text
+    }
+
+    fn render_ascii(&self) -> String {
+        let mut text = String::new();
+        if self.modifiers.control {
+            text.push_str("ctrl-");
+        }
+        if self.modifiers.alt {
+            text.push_str("alt-");
+        }
+        if self.modifiers.shift {
+            text.push_str("shift-");
+        }
+        if self.modifiers.platform {
+            #[cfg(target_os = "macos")]
+            text.push_str("cmd-");
+
+            #[cfg(target_os = "linux")]
+            text.push_str("super-");
+
+            #[cfg(target_os = "windows")]
+            text.push_str("win-");
+        }
+        if self.modifiers.function {
+            text.push_str("fn-");
+        }
+        // Keys named like a modifier need the literal prefix to parse back.
+        if matches!(
+            self.key.as_str(),
+            "ctrl" | "alt" | "shift" | "fn" | "cmd" | "super" | "win"
+        ) {
+            text.push_str("key:");
+        }
+        text.push_str(&self.key);
+        text
+    }
+
+    fn render_verbose(&self) -> String {
+        let mut words = Vec::new();
+        if self.modifiers.control {
+            words.push("Control");
+        }
+        if self.modifiers.alt {
+            words.push(if cfg!(target_os = "macos") {
+                "Option"
+            } else {
+                "Alt"
+            });
+        }
+        if self.modifiers.platform {
+            words.push(if cfg!(target_os = "macos") {
+                "Command"
+            } else if cfg!(target_os = "windows") {
+                "Windows"
+            } else {
+                "Super"
+            });
+        }
+        if self.modifiers.shift {
+            words.push("Shift");
+        }
+        if self.modifiers.function {
+            words.push("Function");
+        }
+
+        let key = match self.key.as_str() {
+            "up" => "Up Arrow".to_string(),
+            "down" => "Down Arrow".to_string(),
+            "left" => "Left Arrow".to_string(),
+            "right" => "Right Arrow".to_string(),
+            "pageup" => "Page Up".to_string(),
+            "pagedown" => "Page Down".to_string(),
+            "-" => "Minus".to_string(),
+            "=" => "Equals".to_string(),
+            "," => "Comma".to_string(),
+            "." => "Period".to_string(),
+            "/" => "Slash".to_string(),
+            key => {
+                let mut chars = key.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
                 }
             }
         };
-        f.write_char(key)
+
+        let mut text = words.join(" ");
+        if !text.is_empty() {
+            text.push(' ');
+        }
+        text.push_str(&key);
+        text
+    }
+
+    /// Renders this keystroke for every platform, for shortcut tables that show
+    /// each platform's form side by side: with glyphs on macOS, such as `⌘S`, and
+    /// spelled out elsewhere, such as `Ctrl+S`, using [`Self::to_doc_string`].
+    pub fn all_platform_displays(&self) -> Vec<(KeystrokePlatform, String)> {
+        vec![
+            (
+                KeystrokePlatform::Mac,
+                self.render_glyphs_for(KeystrokePlatform::Mac),
+            ),
+            (
+                KeystrokePlatform::Linux,
+                self.to_doc_string(KeystrokePlatform::Linux),
+            ),
+            (
+                KeystrokePlatform::Windows,
+                self.to_doc_string(KeystrokePlatform::Windows),
+            ),
+        ]
+    }
+
+    /// Spells out this keystroke as it would be written in documentation for the
+    /// given platform, such as `Cmd+Shift+P` on macOS or `Ctrl+Shift+P` elsewhere,
+    /// without any platform-specific glyphs.
+    ///
+    /// The `cmd` modifier is the primary modifier, which is spelled `Ctrl` outside
+    /// of macOS. Only when combined with `ctrl` is it spelled as the `Super` or
+    /// `Win` key there, so that the two modifiers stay distinct.
+    pub fn to_doc_string(&self, platform: KeystrokePlatform) -> String {
+        let primary_as_control = platform != KeystrokePlatform::Mac
+            && self.modifiers.platform
+            && !self.modifiers.control;
+        let mut parts = Vec::new();
+        if self.modifiers.control || primary_as_control {
+            parts.push("Ctrl".to_string());
+        }
+        if self.modifiers.alt {
+            parts.push(match platform {
+                KeystrokePlatform::Mac => "Option".to_string(),
+                _ => "Alt".to_string(),
+            });
+        }
+        if self.modifiers.platform && !primary_as_control {
+            parts.push(match platform {
+                KeystrokePlatform::Mac => "Cmd".to_string(),
+                KeystrokePlatform::Linux => "Super".to_string(),
+                KeystrokePlatform::Windows => "Win".to_string(),
+            });
+        }
+        if self.modifiers.shift {
+            parts.push("Shift".to_string());
+        }
+        if self.modifiers.function {
+            parts.push("Fn".to_string());
+        }
+
+        parts.push(match self.key.as_str() {
+            "pageup" => "PageUp".to_string(),
+            "pagedown" => "PageDown".to_string(),
+            "escape" => "Esc".to_string(),
+            "+" => "Plus".to_string(),
+            key => {
+                let mut chars = key.chars();
+                match chars.next() {
+                    Some(first) => first.to_uppercase().chain(chars).collect(),
+                    None => String::new(),
+                }
+            }
+        });
+        parts.join("+")
+    }
+}
+
+impl std::fmt::Display for Keystroke {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.render(KeystrokeRenderStyle::Glyph))
+    }
+}
+
+/// Records keystrokes as the user presses them, so that a key binding can be
+/// defined by pressing it instead of typing its name.
+#[derive(Clone, Debug)]
+pub struct KeystrokeRecorder {
+    keystrokes: Vec<Keystroke>,
+    stop_keystroke: Keystroke,
+    recording: bool,
+}
+
+impl KeystrokeRecorder {
+    /// Creates a recorder that stops when `stop_keystroke` is pressed. That
+    /// keystroke is not recorded itself.
+    pub fn new(stop_keystroke: Keystroke) -> Self {
+        Self {
+            keystrokes: Vec::new(),
+            stop_keystroke,
+            recording: true,
+        }
+    }
+
+    /// Records a keystroke, returning whether the recorder is still recording
+    /// afterwards. Keystrokes pressed after recording stopped are ignored.
+    pub fn record(&mut self, keystroke: &Keystroke) -> bool {
+        if self.recording {
+            if keystroke.modifiers == self.stop_keystroke.modifiers
+                && keystroke.key == self.stop_keystroke.key
+            {
+                self.recording = false;
+            } else {
+                self.keystrokes.push(keystroke.clone());
+            }
+        }
+        self.recording
+    }
+
+    /// Whether the stop keystroke hasn't been pressed yet.
+    pub fn is_recording(&self) -> bool {
+        self.recording
+    }
+
+    /// The keystrokes recorded so far, as reported by the platform.
+    pub fn keystrokes(&self) -> &[Keystroke] {
+        &self.keystrokes
+    }
+
+    /// The recorded keystrokes in the keymap syntax understood by [`Keystroke::parse`],
+    /// separated by spaces, such as `ctrl-k ctrl-s`.
+    pub fn binding(&self) -> String {
+        self.keystrokes
+            .iter()
+            .map(|keystroke| keystroke.render(KeystrokeRenderStyle::Ascii))
+            .collect::<Vec<_>>()
+            .join(" ")
     }
 }
 
@@ -366,3 +1242,651 @@ impl Modifiers {
             && (other.function || !self.function)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_dead_key() {
+        // The acute accent key on a German layout produces no text until the next key.
+        let dead_key = Keystroke {
+            modifiers: Modifiers::none(),
+            key: "´".into(),
+            ime_key: Some("".into()),
+        };
+        assert!(dead_key.is_dead_key());
+
+        let combining_key = Keystroke {
+            modifiers: Modifiers::none(),
+            key: "´".into(),
+            ime_key: Some("\u{0301}".into()),
+        };
+        assert!(combining_key.is_dead_key());
+
+        assert!(!Keystroke::parse("e->é").unwrap().is_dead_key());
+        assert!(!Keystroke::parse("a").unwrap().is_dead_key());
+        assert!(!Keystroke::parse("cmd-a").unwrap().is_dead_key());
+    }
+
+    #[test]
+    fn test_parse_literal_key() {
+        assert_eq!(
+            Keystroke::parse("key:shift").unwrap(),
+            Keystroke {
+                modifiers: Modifiers::none(),
+                key: "shift".into(),
+                ime_key: None,
+            }
+        );
+        assert_eq!(
+            Keystroke::parse("ctrl-key:alt").unwrap(),
+            Keystroke {
+                modifiers: Modifiers::control(),
+                key: "alt".into(),
+                ime_key: None,
+            }
+        );
+        assert!(Keystroke::parse("key:shift-a").is_err());
+    }
+
+    #[test]
+    fn test_matches_primary() {
+        let keystroke = |source| Keystroke::parse(source).unwrap();
+
+        assert!(keystroke("cmd-c").matches_primary(&keystroke("ctrl-c")));
+        assert!(keystroke("ctrl-shift-p").matches_primary(&keystroke("cmd-shift-p")));
+        assert!(keystroke("cmd-c").matches_primary(&keystroke("cmd-c")));
+        assert!(!keystroke("cmd-c").matches_primary(&keystroke("ctrl-v")));
+        assert!(!keystroke("cmd-c").matches_primary(&keystroke("alt-c")));
+        assert!(!keystroke("cmd-c").matches_primary(&keystroke("ctrl-shift-c")));
+        assert!(!keystroke("ctrl-cmd-c").matches_primary(&keystroke("cmd-c")));
+    }
+
+    #[test]
+    fn test_without_navigation_function_modifier() {
+        assert_eq!(
+            Keystroke::parse("cmd-fn-left")
+                .unwrap()
+                .without_navigation_function_modifier(),
+            Some(Keystroke::parse("cmd-left").unwrap())
+        );
+        assert_eq!(
+            Keystroke::parse("fn-pagedown")
+                .unwrap()
+                .without_navigation_function_modifier(),
+            Some(Keystroke::parse("pagedown").unwrap())
+        );
+        assert_eq!(
+            Keystroke::parse("cmd-left")
+                .unwrap()
+                .without_navigation_function_modifier(),
+            None
+        );
+        assert_eq!(
+            Keystroke::parse("fn-a")
+                .unwrap()
+                .without_navigation_function_modifier(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_render() {
+        let keystroke = Keystroke::parse("cmd-shift-a").unwrap();
+
+        #[cfg(target_os = "macos")]
+        let (glyph, ascii, verbose) = ("⌘⇧A", "shift-cmd-a", "Command Shift A");
+        #[cfg(target_os = "linux")]
+        let (glyph, ascii, verbose) = ("❖⇧A", "shift-super-a", "Super Shift A");
+        #[cfg(target_os = "windows")]
+        let (glyph, ascii, verbose) = ("⊞⇧A", "shift-win-a", "Windows Shift A");
+
+        assert_eq!(keystroke.render(KeystrokeRenderStyle::Glyph), glyph);
+        assert_eq!(keystroke.to_string(), glyph);
+        assert_eq!(keystroke.render(KeystrokeRenderStyle::Ascii), ascii);
+        assert_eq!(keystroke.render(KeystrokeRenderStyle::Verbose), verbose);
+
+        // The ASCII rendering parses back to the same keystroke.
+        for source in ["ctrl-alt-pageup", "fn-left", "shift-key:shift"] {
+            let keystroke = Keystroke::parse(source).unwrap();
+            assert_eq!(
+                Keystroke::parse(&keystroke.render(KeystrokeRenderStyle::Ascii)).unwrap(),
+                keystroke
+            );
+        }
+        assert_eq!(
+            Keystroke::parse("ctrl-pagedown")
+                .unwrap()
+                .render(KeystrokeRenderStyle::Verbose),
+            "Control Page Down"
+        );
+    }
+
+    #[test]
+    fn test_clipboard_shortcuts() {
+        let keystroke = |source| Keystroke::parse(source).unwrap();
+
+        #[cfg(target_os = "macos")]
+        {
+            assert!(keystroke("cmd-v").is_paste());
+            assert!(keystroke("cmd-c").is_copy());
+            assert!(keystroke("cmd-x").is_cut());
+            assert!(!keystroke("ctrl-v").is_paste());
+            assert!(!keystroke("shift-insert").is_paste());
+        }
+
+        #[cfg(not(target_os = "macos"))]
+        {
+            assert!(keystroke("ctrl-v").is_paste());
+            assert!(keystroke("ctrl-c").is_copy());
+            assert!(keystroke("ctrl-x").is_cut());
+            assert!(!keystroke("cmd-v").is_paste());
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            assert!(keystroke("shift-insert").is_paste());
+            assert!(keystroke("ctrl-insert").is_copy());
+            assert!(keystroke("shift-delete").is_cut());
+        }
+
+        #[cfg(target_os = "windows")]
+        assert!(!keystroke("shift-insert").is_paste());
+
+        assert!(!keystroke("v").is_paste());
+        assert!(!keystroke("cmd-shift-v").is_paste());
+        assert!(!keystroke("ctrl-shift-v").is_paste());
+        assert!(!keystroke("insert").is_paste());
+        assert!(!keystroke("cmd-c").is_paste());
+    }
+
+    #[test]
+    fn test_parse_with_features() {
+        let (keystroke, features) = Keystroke::parse_with_features("super-s").unwrap();
+        assert_eq!(keystroke, Keystroke::parse("cmd-s").unwrap());
+        assert_eq!(features.as_slice(), [KeystrokeSyntaxFeature::PlatformAlias]);
+        assert!(!KeystrokeSyntaxFeature::PlatformAlias.requires_newer_grammar());
+        assert!(KeystrokeSyntaxFeature::LiteralKey.requires_newer_grammar());
+
+        let (_, features) = Keystroke::parse_with_features("win-shift-key:shift").unwrap();
+        assert_eq!(
+            features.as_slice(),
+            [
+                KeystrokeSyntaxFeature::PlatformAlias,
+                KeystrokeSyntaxFeature::LiteralKey
+            ]
+        );
+
+        let (_, features) = Keystroke::parse_with_features("cmd-shift-a").unwrap();
+        assert!(features.is_empty());
+    }
+
+    #[test]
+    fn test_keystroke_recorder() {
+        let mut recorder = KeystrokeRecorder::new(Keystroke::parse("escape").unwrap());
+        assert!(recorder.record(&Keystroke::parse("ctrl-k").unwrap()));
+        assert!(recorder.record(&Keystroke::parse("alt-s->ß").unwrap()));
+        assert!(recorder.record(&Keystroke::parse("ctrl-shift-key:shift").unwrap()));
+        assert!(!recorder.record(&Keystroke::parse("escape").unwrap()));
+        assert!(!recorder.record(&Keystroke::parse("ctrl-x").unwrap()));
+        assert!(!recorder.is_recording());
+
+        // The raw keystrokes keep what the IME produced.
+        assert_eq!(recorder.keystrokes().len(), 3);
+        assert_eq!(recorder.keystrokes()[1].ime_key.as_deref(), Some("ß"));
+
+        let binding = recorder.binding();
+        assert_eq!(binding, "ctrl-k alt-s ctrl-shift-key:shift");
+        let parsed = binding
+            .split(' ')
+            .map(|source| Keystroke::parse(source).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            parsed,
+            recorder
+                .keystrokes()
+                .iter()
+                .map(|keystroke| Keystroke {
+                    ime_key: None,
+                    ..keystroke.clone()
+                })
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_parse_with_aliases() {
+        let mut aliases = KeystrokeAliasTable::default();
+        aliases.insert("hyper", "ctrl-alt-shift-cmd");
+
+        assert_eq!(
+            Keystroke::parse_with_aliases("hyper-x", &aliases).unwrap(),
+            Keystroke {
+                modifiers: Modifiers {
+                    control: true,
+                    alt: true,
+                    shift: true,
+                    platform: true,
+                    function: false,
+                },
+                key: "x".into(),
+                ime_key: None,
+            }
+        );
+        assert_eq!(
+            Keystroke::parse_with_aliases("fn-hyper-x", &aliases).unwrap(),
+            Keystroke::parse("fn-ctrl-alt-shift-cmd-x").unwrap()
+        );
+        // Aliases only apply to modifiers, not to the key.
+        assert_eq!(
+            Keystroke::parse_with_aliases("ctrl-hyper", &aliases).unwrap(),
+            Keystroke::parse("ctrl-hyper").unwrap()
+        );
+        assert_eq!(
+            Keystroke::parse_with_aliases("hyper--", &aliases).unwrap(),
+            Keystroke::parse("ctrl-alt-shift-cmd--").unwrap()
+        );
+    }
+
+    #[test]
+    fn test_for_dispatch() {
+        let space = Keystroke::for_dispatch("space").unwrap();
+        assert_eq!(space.key, "space");
+        assert_eq!(space.ime_key.as_deref(), Some(" "));
+
+        let shift_a = Keystroke::for_dispatch("shift-a").unwrap();
+        assert!(shift_a.modifiers.shift);
+        assert_eq!(shift_a.ime_key.as_deref(), Some("A"));
+
+        // Shortcuts don't insert any text.
+        let cmd_s = Keystroke::for_dispatch("cmd-s").unwrap();
+        assert!(cmd_s.modifiers.platform);
+        assert_eq!(cmd_s.ime_key, None);
+    }
+
+    #[test]
+    fn test_key_icon_name() {
+        let icon = |source: &str| Keystroke::parse(source).unwrap().key_icon_name();
+        assert_eq!(icon("left"), Some("icons/arrow_left.svg"));
+        assert_eq!(icon("cmd-shift-up"), Some("icons/arrow_up.svg"));
+        assert_eq!(icon("shift"), Some("icons/shift.svg"));
+        assert_eq!(icon("cmd"), Some("icons/command.svg"));
+        assert_eq!(icon("alt"), Some("icons/option.svg"));
+        assert_eq!(icon("cmd-a"), None);
+        assert_eq!(icon("f5"), None);
+    }
+
+    #[test]
+    fn test_shadows_system_shortcut() {
+        let shadows = |source: &str, platform| {
+            Keystroke::parse(source)
+                .unwrap()
+                .shadows_system_shortcut(platform)
+        };
+        assert_eq!(
+            shadows("cmd-space", KeystrokePlatform::Mac),
+            Some("Spotlight")
+        );
+        assert_eq!(shadows("cmd-shift-space", KeystrokePlatform::Mac), None);
+        assert_eq!(shadows("alt-tab", KeystrokePlatform::Mac), None);
+        assert_eq!(
+            shadows("alt-tab", KeystrokePlatform::Linux),
+            Some("Switch applications")
+        );
+        assert_eq!(
+            shadows("alt-tab", KeystrokePlatform::Windows),
+            Some("Switch windows")
+        );
+        // Keystrokes are compared as they are matched against bindings.
+        assert_eq!(
+            shadows("ctrl-fn-left", KeystrokePlatform::Mac),
+            Some("Move left a space")
+        );
+        assert_eq!(shadows("super-l", KeystrokePlatform::Windows), Some("Lock"));
+    }
+
+    #[test]
+    fn test_parse_lenient() {
+        for (legacy, modern) in [
+            ("ctrl+shift+a", "ctrl-shift-a"),
+            ("control-c", "ctrl-c"),
+            ("option-left", "alt-left"),
+            ("opt-x", "alt-x"),
+            ("command-s", "cmd-s"),
+            ("command+option+i", "cmd-alt-i"),
+        ] {
+            assert_eq!(modernize_legacy_keystroke(legacy), modern);
+            assert_eq!(
+                Keystroke::parse_lenient(legacy).unwrap(),
+                Keystroke::parse(modern).unwrap()
+            );
+        }
+
+        // Current forms are left alone, including keys that look like legacy forms.
+        for source in ["ctrl-+", "+", "cmd-shift-=", "control", "win-k"] {
+            assert_eq!(modernize_legacy_keystroke(source), source);
+            assert_eq!(
+                Keystroke::parse_lenient(source).unwrap(),
+                Keystroke::parse(source).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_base_key() {
+        let option_s = Keystroke::parse("alt-s->ß").unwrap();
+        assert_eq!(option_s.base_key(), "s");
+        assert_eq!(option_s.ime_key.as_deref(), Some("ß"));
+
+        assert_eq!(Keystroke::parse("cmd-k").unwrap().base_key(), "k");
+    }
+
+    #[test]
+    fn test_special_key_names() {
+        for &name in special_key_names() {
+            let keystroke = Keystroke::parse(name).unwrap();
+            assert_eq!(keystroke.key, name);
+            assert_eq!(
+                Keystroke::parse(&keystroke.render(KeystrokeRenderStyle::Ascii)).unwrap(),
+                keystroke
+            );
+
+            let ime_key = keystroke.with_simulated_ime().ime_key;
+            match name {
+                "space" => assert_eq!(ime_key.as_deref(), Some(" ")),
+                "tab" => assert_eq!(ime_key.as_deref(), Some("\t")),
+                "enter" => assert_eq!(ime_key.as_deref(), Some("\n")),
+                _ => assert_eq!(ime_key, None, "{name} shouldn't type any text"),
+            }
+        }
+
+        let keystroke = Keystroke::parse("f13").unwrap();
+        assert_eq!(keystroke.with_simulated_ime().ime_key, None);
+        for key in ["kp_enter", "f24", "volumeup"] {
+            assert!(special_key_names().contains(&key), "{key}");
+            assert!(!Keystroke::parse(key).unwrap().is_commonly_available());
+        }
+    }
+
+    #[test]
+    fn test_is_layout_sensitive() {
+        for source in ["$", "cmd-@", "ctrl-[", "alt-\\"] {
+            assert!(
+                Keystroke::parse(source).unwrap().is_layout_sensitive(),
+                "{source}"
+            );
+        }
+        for source in ["a", "cmd-s", "ctrl-1", "enter", "f5", "-", "cmd-,"] {
+            assert!(
+                !Keystroke::parse(source).unwrap().is_layout_sensitive(),
+                "{source}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_is_prefix_of() {
+        let parse = |source: &str| {
+            source
+                .split_whitespace()
+                .map(|keystroke| Keystroke::parse(keystroke).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let binding = parse("ctrl-k ctrl-c");
+
+        assert!(is_prefix_of(&parse("ctrl-k"), &binding));
+        assert!(is_prefix_of(&parse("ctrl-k->k"), &binding));
+        assert!(is_prefix_of(&[], &binding));
+        assert!(!is_prefix_of(&parse("ctrl-j"), &binding));
+        assert!(!is_prefix_of(&parse("ctrl-k ctrl-c"), &binding));
+        assert!(!is_prefix_of(&parse("ctrl-k ctrl-c ctrl-d"), &binding));
+    }
+
+    #[test]
+    fn test_to_doc_string() {
+        let doc =
+            |source: &str, platform| Keystroke::parse(source).unwrap().to_doc_string(platform);
+
+        assert_eq!(doc("cmd-shift-p", KeystrokePlatform::Mac), "Cmd+Shift+P");
+        assert_eq!(
+            doc("ctrl-alt-pageup", KeystrokePlatform::Mac),
+            "Ctrl+Option+PageUp"
+        );
+        assert_eq!(doc("escape", KeystrokePlatform::Mac), "Esc");
+        assert_eq!(doc("cmd-+", KeystrokePlatform::Mac), "Cmd+Plus");
+
+        assert_eq!(
+            doc("ctrl-shift-p", KeystrokePlatform::Linux),
+            "Ctrl+Shift+P"
+        );
+        assert_eq!(doc("cmd-shift-p", KeystrokePlatform::Linux), "Ctrl+Shift+P");
+        assert_eq!(doc("cmd-s", KeystrokePlatform::Windows), "Ctrl+S");
+        assert_eq!(doc("alt-f4", KeystrokePlatform::Windows), "Alt+F4");
+
+        // Together with `ctrl`, the primary modifier is the Super or Win key.
+        assert_eq!(
+            doc("ctrl-super-enter", KeystrokePlatform::Linux),
+            "Ctrl+Super+Enter"
+        );
+        assert_eq!(doc("ctrl-win-d", KeystrokePlatform::Windows), "Ctrl+Win+D");
+    }
+
+    #[test]
+    fn test_is_commonly_available() {
+        let available = |source: &str| Keystroke::parse(source).unwrap().is_commonly_available();
+
+        assert!(available("a"));
+        assert!(available("cmd-shift-p"));
+        assert!(available("f12"));
+        assert!(available("enter"));
+        assert!(available("f"));
+
+        assert!(!available("kp_enter"));
+        assert!(!available("f20"));
+        assert!(!available("f13"));
+        assert!(!available("shift-volumeup"));
+    }
+
+    #[test]
+    fn test_is_standard_action_shortcut() {
+        let is_shortcut = |source: &str, action, platform| {
+            Keystroke::parse(source)
+                .unwrap()
+                .is_standard_action_shortcut(action, platform)
+        };
+
+        assert!(is_shortcut(
+            "cmd-n",
+            StandardAction::NewWindow,
+            KeystrokePlatform::Mac
+        ));
+        assert!(is_shortcut(
+            "cmd-w",
+            StandardAction::Close,
+            KeystrokePlatform::Mac
+        ));
+        assert!(is_shortcut(
+            "cmd-q",
+            StandardAction::Quit,
+            KeystrokePlatform::Mac
+        ));
+        assert!(is_shortcut(
+            "cmd-shift-z",
+            StandardAction::Redo,
+            KeystrokePlatform::Mac
+        ));
+        assert!(!is_shortcut(
+            "ctrl-w",
+            StandardAction::Close,
+            KeystrokePlatform::Mac
+        ));
+
+        assert!(is_shortcut(
+            "ctrl-n",
+            StandardAction::NewWindow,
+            KeystrokePlatform::Linux
+        ));
+        assert!(is_shortcut(
+            "ctrl-q",
+            StandardAction::Quit,
+            KeystrokePlatform::Linux
+        ));
+        assert!(!is_shortcut(
+            "ctrl-m",
+            StandardAction::Minimize,
+            KeystrokePlatform::Linux
+        ));
+
+        assert!(is_shortcut(
+            "alt-f4",
+            StandardAction::Quit,
+            KeystrokePlatform::Windows
+        ));
+        assert!(is_shortcut(
+            "ctrl-y",
+            StandardAction::Redo,
+            KeystrokePlatform::Windows
+        ));
+        assert!(is_shortcut(
+            "ctrl-shift-z",
+            StandardAction::Redo,
+            KeystrokePlatform::Windows
+        ));
+        assert!(!is_shortcut(
+            "ctrl-q",
+            StandardAction::Quit,
+            KeystrokePlatform::Windows
+        ));
+    }
+
+    #[test]
+    fn test_format_continuations() {
+        let parse = |source: &str| {
+            source
+                .split(' ')
+                .map(|keystroke| Keystroke::parse(keystroke).unwrap())
+                .collect::<Vec<_>>()
+        };
+        let candidates = [
+            parse("ctrl-k ctrl-s"),
+            parse("ctrl-k z"),
+            parse("ctrl-k ctrl-c ctrl-u"),
+            parse("ctrl-shift-k"),
+            parse("ctrl-k"),
+        ];
+
+        assert_eq!(
+            format_continuations(&parse("ctrl-k"), &candidates, KeystrokeRenderStyle::Ascii),
+            ["ctrl-k → ctrl-s", "ctrl-k → z", "ctrl-k → ctrl-c ctrl-u"]
+        );
+        assert_eq!(
+            format_continuations(
+                &parse("ctrl-k ctrl-c"),
+                &candidates,
+                KeystrokeRenderStyle::Ascii
+            ),
+            ["ctrl-k ctrl-c → ctrl-u"]
+        );
+        assert!(
+            format_continuations(&parse("ctrl-j"), &candidates, KeystrokeRenderStyle::Ascii)
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_with_us_shifted_symbol_canonicalized() {
+        let canonical = |source: &str| {
+            Keystroke::parse(source)
+                .unwrap()
+                .with_us_shifted_symbol_canonicalized()
+        };
+
+        for (symbol, base) in [
+            ("!", "shift-1"),
+            ("ctrl-@", "ctrl-shift-2"),
+            ("cmd-?", "cmd-shift-/"),
+            ("shift-{", "shift-["),
+            ("alt-|", "alt-shift-\\"),
+            ("_", "shift--"),
+        ] {
+            assert_eq!(canonical(symbol), canonical(base), "{symbol} != {base}");
+            assert_eq!(canonical(symbol), Keystroke::parse(base).unwrap());
+        }
+
+        assert_eq!(canonical("a"), Keystroke::parse("a").unwrap());
+        assert_ne!(canonical("!"), canonical("1"));
+    }
+
+    #[test]
+    fn test_conflicts_with_text_input() {
+        let conflicts = |source: &str| {
+            Keystroke::parse(source)
+                .unwrap()
+                .conflicts_with_text_input()
+        };
+
+        assert!(conflicts("a"));
+        assert!(conflicts("shift-a"));
+        assert!(conflicts("!"));
+        assert!(conflicts("space"));
+
+        // A modifier pressed on its own doesn't type its name.
+        for modifier in ["shift", "ctrl", "alt", "cmd", "fn"] {
+            assert!(!conflicts(modifier), "{modifier}");
+        }
+
+        assert!(!conflicts("cmd-a"));
+        assert!(!conflicts("ctrl-a"));
+        assert!(!conflicts("escape"));
+        assert!(!conflicts("f5"));
+        assert!(!conflicts("shift-left"));
+    }
+
+    #[test]
+    fn test_all_platform_displays() {
+        // `cmd` is the primary modifier, which is `ctrl` outside of macOS.
+        let mut aliases = KeystrokeAliasTable::default();
+        aliases.insert("primary", "cmd");
+        assert_eq!(
+            Keystroke::parse_with_aliases("primary-s", &aliases)
+                .unwrap()
+                .all_platform_displays(),
+            [
+                (KeystrokePlatform::Mac, "⌘S".to_string()),
+                (KeystrokePlatform::Linux, "Ctrl+S".to_string()),
+                (KeystrokePlatform::Windows, "Ctrl+S".to_string()),
+            ]
+        );
+        assert_eq!(
+            Keystroke::parse("ctrl-cmd-s")
+                .unwrap()
+                .all_platform_displays(),
+            [
+                (KeystrokePlatform::Mac, "^⌘S".to_string()),
+                (KeystrokePlatform::Linux, "Ctrl+Super+S".to_string()),
+                (KeystrokePlatform::Windows, "Ctrl+Win+S".to_string()),
+            ]
+        );
+        assert_eq!(
+            Keystroke::parse("cmd-shift-p")
+                .unwrap()
+                .all_platform_displays(),
+            [
+                (KeystrokePlatform::Mac, "⌘⇧P".to_string()),
+                (KeystrokePlatform::Linux, "Ctrl+Shift+P".to_string()),
+                (KeystrokePlatform::Windows, "Ctrl+Shift+P".to_string()),
+            ]
+        );
+        assert_eq!(
+            Keystroke::parse("alt-enter")
+                .unwrap()
+                .all_platform_displays(),
+            [
+                (KeystrokePlatform::Mac, "⌥enter".to_string()),
+                (KeystrokePlatform::Linux, "Alt+Enter".to_string()),
+                (KeystrokePlatform::Windows, "Alt+Enter".to_string()),
+            ]
+        );
+    }
+}
@@ -1,10 +1,10 @@
 use anyhow::{anyhow, Context, Result};
 use futures::{io::BufReader, stream::BoxStream, AsyncBufReadExt, AsyncReadExt, StreamExt};
-use http::{AsyncBody, HttpClient, Method, Request as HttpRequest};
-use isahc::config::Configurable;
+use http::{AsyncBody, HttpClient, Method, Request as HttpRequest, StatusCode};
+use isahc::config::{CaCertificate, Configurable, SslOption, VersionNegotiation};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::{convert::TryFrom, time::Duration};
+use std::{collections::HashMap, convert::TryFrom, fmt, path::PathBuf, sync::Arc, time::Duration};
 
 pub const OLLAMA_API_URL: &str = "http://localhost:11434";
 
@@ -14,6 +14,7 @@ pub enum Role {
     User,
     Assistant,
     System,
+    Tool,
 }
 
 impl TryFrom<String> for Role {
@@ -24,6 +25,7 @@ impl TryFrom<String> for Role {
             "user" => Ok(Self::User),
             "assistant" => Ok(Self::Assistant),
             "system" => Ok(Self::System),
+            "tool" => Ok(Self::Tool),
             _ => Err(anyhow!("invalid role '{value}'")),
         }
     }
@@ -35,6 +37,7 @@ impl From<Role> for String {
             Role::User => "user".to_owned(),
             Role::Assistant => "assistant".to_owned(),
             Role::System => "system".to_owned(),
+            Role::Tool => "tool".to_owned(),
         }
     }
 }
@@ -50,7 +53,7 @@ pub enum KeepAlive {
 
 impl KeepAlive {
     /// Keep model alive until a new model is loaded or until Ollama shuts down
-    fn indefinite() -> Self {
+    pub fn indefinite() -> Self {
         Self::Seconds(-1)
     }
 }
@@ -61,12 +64,91 @@ impl Default for KeepAlive {
     }
 }
 
+/// TLS options used when connecting to an Ollama server over https.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct TlsConfig {
+    /// Path to a PEM file with the CA certificates to trust, for servers behind
+    /// a private certificate authority.
+    pub ca_bundle_path: Option<PathBuf>,
+    /// Skip verifying the server's certificate entirely.
+    ///
+    /// This is insecure and should only be used as a last resort.
+    #[serde(default)]
+    pub danger_accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    /// Builds an HTTP client that connects using these TLS options.
+    pub fn build_client(&self, proxy: Option<&str>) -> Result<Arc<dyn HttpClient>> {
+        let mut builder = isahc::HttpClient::builder()
+            .connect_timeout(Duration::from_secs(5))
+            .proxy(proxy.and_then(|proxy| proxy.parse().ok()));
+        if let Some(ca_bundle_path) = &self.ca_bundle_path {
+            builder = builder.ssl_ca_certificate(CaCertificate::file(ca_bundle_path));
+        }
+        if self.danger_accept_invalid_certs {
+            builder = builder.ssl_options(SslOption::DANGER_ACCEPT_INVALID_CERTS);
+        }
+        Ok(Arc::new(builder.build()?))
+    }
+}
+
+/// The HTTP version used to stream completions from an Ollama server.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+    /// Use whichever version the client negotiates with the server.
+    #[default]
+    Auto,
+    /// Always use HTTP/1.1, for proxies that buffer streamed HTTP/2 responses.
+    Http1,
+}
+
 #[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
 #[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
 pub struct Model {
     pub name: String,
     pub max_tokens: usize,
     pub keep_alive: Option<KeepAlive>,
+    /// The adapter, such as a LoRA fine-tune, to apply on top of the model.
+    pub adapter: Option<String>,
+    /// The name shown for the model instead of its name on the server.
+    pub display_name: Option<String>,
+    /// The temperature to sample the model with, instead of the one requested.
+    pub temperature: Option<f32>,
+    /// How to run the model and sample its tokens, instead of the server's defaults.
+    #[serde(default)]
+    pub options: ModelOptions,
+    /// What the server reported about the model, which can't be configured.
+    #[serde(skip)]
+    pub metadata: ModelMetadata,
+}
+
+/// What the server reports about a model it lists in `/api/tags` and describes in
+/// `/api/show`. Unknown for models that aren't listed by the server.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ModelMetadata {
+    /// When the model was last pulled or changed on the server, as an RFC 3339
+    /// timestamp.
+    pub modified_at: Option<String>,
+    /// A short description of the model shown alongside its name, such as
+    /// `llama 8.0B (Q4_0)`.
+    pub description: Option<String>,
+    /// The number of parameters of the model, such as `8.0B`.
+    pub parameter_size: Option<String>,
+    /// How the model's weights are quantized, such as `Q4_0`.
+    pub quantization_level: Option<String>,
+    /// The digest of the model's weights, which is the same for every tag of the
+    /// same model.
+    pub digest: Option<String>,
+    /// The family of the model's architecture, such as `llama`, which determines
+    /// how it tokenizes text.
+    pub family: Option<String>,
+    /// The longest context the model was trained for, in tokens, which can be more
+    /// than `max_tokens`.
+    pub context_length: Option<usize>,
 }
 
 impl Model {
@@ -75,6 +157,11 @@ impl Model {
             name: name.to_owned(),
             max_tokens: 2048,
             keep_alive: Some(KeepAlive::indefinite()),
+            adapter: None,
+            display_name: None,
+            temperature: None,
+            options: ModelOptions::default(),
+            metadata: ModelMetadata::default(),
         }
     }
 
@@ -83,39 +170,274 @@ impl Model {
     }
 
     pub fn display_name(&self) -> &str {
-        &self.name
+        self.display_name.as_deref().unwrap_or(&self.name)
     }
 
     pub fn max_token_count(&self) -> usize {
         self.max_tokens
     }
+
+    pub fn description(&self) -> Option<&str> {
+        self.metadata.description.as_deref()
+    }
+
+    /// Roughly estimates the memory needed to run the model from its parameter
+    /// size and quantization, including some room for the context. Returns `None`
+    /// when either is unknown.
+    pub fn estimated_memory_bytes(&self) -> Option<u64> {
+        let parameter_size = self.metadata.parameter_size.as_deref()?.trim();
+        let (count, scale) = match parameter_size.char_indices().last()? {
+            (ix, 'B') => (&parameter_size[..ix], 1e9),
+            (ix, 'M') => (&parameter_size[..ix], 1e6),
+            _ => return None,
+        };
+        let parameters = count.parse::<f64>().ok()? * scale;
+
+        // Quantized weights also store a scale per block, adding about half a bit.
+        let quantization_level = self.metadata.quantization_level.as_deref()?.to_uppercase();
+        let bits_per_weight = match quantization_level.as_str() {
+            "F32" => 32.0,
+            "F16" | "BF16" => 16.0,
+            level => {
+                let bits = level.strip_prefix('Q')?.chars().next()?.to_digit(10)?;
+                bits as f64 + 0.5
+            }
+        };
+
+        const CONTEXT_OVERHEAD: f64 = 1.2;
+        Some((parameters * bits_per_weight / 8.0 * CONTEXT_OVERHEAD) as u64)
+    }
 }
 
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
 #[serde(tag = "role", rename_all = "lowercase")]
 pub enum ChatMessage {
-    Assistant { content: String },
-    User { content: String },
-    System { content: String },
+    Assistant {
+        content: String,
+        /// The tools the model called, which streaming servers send in a chunk of
+        /// their own.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        tool_calls: Vec<OllamaToolCall>,
+    },
+    User {
+        content: String,
+        /// Base64-encoded images attached to the message, for models that
+        /// support vision.
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        images: Vec<String>,
+    },
+    System {
+        content: String,
+    },
+    /// The result of a tool called by the model.
+    Tool {
+        content: String,
+        /// The name of the tool that produced the result, for servers that match
+        /// results to calls by name rather than by order.
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        tool_name: Option<String>,
+    },
+}
+
+impl ChatMessage {
+    pub fn content(&self) -> &str {
+        match self {
+            ChatMessage::Assistant { content, .. }
+            | ChatMessage::User { content, .. }
+            | ChatMessage::System { content }
+            | ChatMessage::Tool { content, .. } => content,
+        }
+    }
+}
+
+/// A call the model made to one of the tools in the request.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct OllamaToolCall {
+    pub function: OllamaFunctionCall,
 }
 
-#[derive(Serialize)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct OllamaFunctionCall {
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// A tool the model may call, for models that support tools.
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum OllamaTool {
+    Function { function: OllamaFunctionTool },
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq)]
+pub struct OllamaFunctionTool {
+    pub name: String,
+    pub description: String,
+    /// A JSON schema of the function's arguments.
+    pub parameters: serde_json::Value,
+}
+
+#[derive(Clone, Serialize)]
 pub struct ChatRequest {
     pub model: String,
     pub messages: Vec<ChatMessage>,
     pub stream: bool,
     pub keep_alive: KeepAlive,
     pub options: Option<ChatOptions>,
+    /// Asks the server to report the log probability of every generated token.
+    /// Servers that don't support it ignore the field.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub logprobs: bool,
+    /// The adapter to apply to the model for this request, for servers that
+    /// support selecting one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub adapter: Option<String>,
+    /// A JSON schema that the model's output must conform to, or `"json"` for
+    /// any valid JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<serde_json::Value>,
+    /// Whether to drop the oldest part of the conversation when it no longer fits
+    /// in the context window, instead of failing the request. When unset, the
+    /// server decides.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub shift: Option<bool>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<OllamaTool>,
+    /// Asks the server to only render the prompt with the model's chat template
+    /// instead of running the model, see [`render_chat_prompt`].
+    #[serde(
+        rename = "_debug_render_only",
+        skip_serializing_if = "std::ops::Not::not"
+    )]
+    pub debug_render_only: bool,
+}
+
+/// A request to continue a prompt rather than a conversation, such as the code
+/// before the cursor, optionally filling in the text up to `suffix`.
+#[derive(Clone, Serialize)]
+pub struct GenerateRequest {
+    pub model: String,
+    pub prompt: String,
+    /// The text after the completion, for models that can fill in the middle.
+    /// The server applies the model's template to combine it with the prompt.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// Whether to send the prompt to the model as is, for prompts that already
+    /// contain the model's special tokens. Raw prompts can't have a suffix.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub raw: bool,
+    pub stream: bool,
+    pub keep_alive: KeepAlive,
+    pub options: Option<ChatOptions>,
+}
+
+#[derive(Deserialize)]
+pub struct GenerateResponseDelta {
+    #[allow(unused)]
+    pub model: String,
+    #[allow(unused)]
+    pub created_at: String,
+    /// The text generated since the previous chunk.
+    #[serde(default)]
+    pub response: String,
+    #[allow(unused)]
+    pub done: bool,
+}
+
+/// Options that change how the server runs a model and samples its tokens, left
+/// to the model's defaults when unset.
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub struct ModelOptions {
+    /// The number of the model's layers to offload to the GPU. Zero runs the model
+    /// entirely on the CPU.
+    pub num_gpu: Option<usize>,
+    /// The number of threads to run the model with on the CPU.
+    pub num_thread: Option<usize>,
+    /// The most tokens to generate in a response, or -1 for no limit.
+    pub num_predict: Option<isize>,
+    /// Samples only from the most likely tokens whose probabilities add up to this.
+    pub top_p: Option<f32>,
+    /// Samples only from the given number of most likely tokens.
+    pub top_k: Option<usize>,
+    /// How strongly to penalize repeated tokens, where 1.0 doesn't penalize them.
+    pub repeat_penalty: Option<f32>,
+    /// The seed of the random number generator, for responses that can be
+    /// reproduced.
+    pub seed: Option<i64>,
+    /// The version of Mirostat sampling to use, which keeps the perplexity of the
+    /// response steady instead of sampling from `top_k` and `top_p`. Zero
+    /// disables it.
+    pub mirostat: Option<u8>,
+    /// The perplexity Mirostat keeps the response at, where lower values give more
+    /// focused text.
+    pub mirostat_tau: Option<f32>,
+    /// How quickly Mirostat responds to the text generated so far.
+    pub mirostat_eta: Option<f32>,
+}
+
+impl ModelOptions {
+    /// Returns these options with the ones left unset taken from `fallback`.
+    pub fn or(self, fallback: Self) -> Self {
+        Self {
+            num_gpu: self.num_gpu.or(fallback.num_gpu),
+            num_thread: self.num_thread.or(fallback.num_thread),
+            num_predict: self.num_predict.or(fallback.num_predict),
+            top_p: self.top_p.or(fallback.top_p),
+            top_k: self.top_k.or(fallback.top_k),
+            repeat_penalty: self.repeat_penalty.or(fallback.repeat_penalty),
+            seed: self.seed.or(fallback.seed),
+            mirostat: self.mirostat.or(fallback.mirostat),
+            mirostat_tau: self.mirostat_tau.or(fallback.mirostat_tau),
+            mirostat_eta: self.mirostat_eta.or(fallback.mirostat_eta),
+        }
+    }
+
+    /// Overrides the options of a request with the ones that are set.
+    pub fn apply_to(self, options: &mut ChatOptions) {
+        options.num_gpu = self.num_gpu.or(options.num_gpu);
+        options.num_thread = self.num_thread.or(options.num_thread);
+        options.num_predict = self.num_predict.or(options.num_predict);
+        options.top_p = self.top_p.or(options.top_p);
+        options.top_k = self.top_k.or(options.top_k);
+        options.repeat_penalty = self.repeat_penalty.or(options.repeat_penalty);
+        options.seed = self.seed.or(options.seed);
+        options.mirostat = self.mirostat.or(options.mirostat);
+        options.mirostat_tau = self.mirostat_tau.or(options.mirostat_tau);
+        options.mirostat_eta = self.mirostat_eta.or(options.mirostat_eta);
+    }
 }
 
 // https://github.com/ollama/ollama/blob/main/docs/modelfile.md#valid-parameters-and-values
-#[derive(Serialize, Default)]
+#[derive(Clone, Serialize, Default)]
 pub struct ChatOptions {
     pub num_ctx: Option<usize>,
     pub num_predict: Option<isize>,
     pub stop: Option<Vec<String>>,
     pub temperature: Option<f32>,
     pub top_p: Option<f32>,
+    /// Samples only from the given number of most likely tokens. One always picks
+    /// the most likely token.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_batch: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_thread: Option<usize>,
+    /// The number of the model's layers to offload to the GPU. Zero runs the model
+    /// entirely on the CPU.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_gpu: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat_tau: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mirostat_eta: Option<f32>,
 }
 
 #[derive(Deserialize)]
@@ -124,11 +446,34 @@ pub struct ChatResponseDelta {
     pub model: String,
     #[allow(unused)]
     pub created_at: String,
-    pub message: ChatMessage,
+    /// Absent on the final chunk of a stream, which only carries statistics.
+    #[serde(default)]
+    pub message: Option<ChatMessage>,
     #[allow(unused)]
     pub done_reason: Option<String>,
     #[allow(unused)]
     pub done: bool,
+    #[serde(default)]
+    pub prompt_eval_count: Option<u64>,
+    #[serde(default)]
+    pub eval_count: Option<u64>,
+    /// How long loading the model took, in nanoseconds.
+    #[serde(default)]
+    pub load_duration: Option<u64>,
+    /// How long evaluating the prompt took, in nanoseconds.
+    #[serde(default)]
+    pub prompt_eval_duration: Option<u64>,
+    /// How long generating the response took, in nanoseconds.
+    #[serde(default)]
+    pub eval_duration: Option<u64>,
+    #[serde(default)]
+    pub logprobs: Option<Vec<TokenLogprob>>,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize)]
+pub struct TokenLogprob {
+    pub token: String,
+    pub logprob: f64,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -162,12 +507,131 @@ pub struct ModelDetails {
     pub quantization_level: String,
 }
 
+/// An error response from the Ollama API.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ApiError {
+    /// The server ran out of memory loading the model, which usually means that
+    /// the requested context doesn't fit in the GPU's memory.
+    OutOfMemory {
+        body: String,
+    },
+    /// The server doesn't have the requested model, which has to be pulled first.
+    ModelNotFound {
+        body: String,
+    },
+    Other {
+        status: StatusCode,
+        body: String,
+    },
+}
+
+impl ApiError {
+    fn new(status: StatusCode, body: String) -> Self {
+        let message = body.to_lowercase();
+        if message.contains("out of memory") || message.contains("requires more system memory") {
+            Self::OutOfMemory { body }
+        } else if status == StatusCode::NOT_FOUND && message.contains("model") {
+            Self::ModelNotFound { body }
+        } else {
+            Self::Other { status, body }
+        }
+    }
+}
+
+impl fmt::Display for ApiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OutOfMemory { body } => write!(f, "Ollama ran out of memory: {body}"),
+            Self::ModelNotFound { body } => {
+                write!(f, "Ollama doesn't have the model, pull it first: {body}")
+            }
+            Self::Other { status, body } => {
+                write!(f, "Failed to connect to Ollama API: {status} {body}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// How a request to Ollama failed, for deciding whether to send it again and what
+/// to tell the user.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FailureKind {
+    /// The server couldn't be reached, usually because Ollama isn't running or is
+    /// still starting up.
+    ConnectionRefused,
+    /// The server took too long to respond.
+    Timeout,
+    /// The connection broke while talking to the server.
+    ConnectionReset,
+    /// The server doesn't have the requested model.
+    ModelNotFound,
+    /// The server couldn't fit the model into memory.
+    OutOfMemory,
+    /// The server failed to handle the request, for example because its queue
+    /// was full.
+    ServerError,
+    Other,
+}
+
+impl FailureKind {
+    pub fn of(error: &anyhow::Error) -> Self {
+        if let Some(error) = error.downcast_ref::<ApiError>() {
+            return match error {
+                ApiError::OutOfMemory { .. } => Self::OutOfMemory,
+                ApiError::ModelNotFound { .. } => Self::ModelNotFound,
+                ApiError::Other { status, .. } if status.is_server_error() => Self::ServerError,
+                ApiError::Other { .. } => Self::Other,
+            };
+        }
+        if let Some(error) = error.downcast_ref::<http::Error>() {
+            return match error.kind() {
+                isahc::error::ErrorKind::ConnectionFailed => Self::ConnectionRefused,
+                isahc::error::ErrorKind::Timeout => Self::Timeout,
+                isahc::error::ErrorKind::Io => Self::ConnectionReset,
+                _ => Self::Other,
+            };
+        }
+        Self::Other
+    }
+
+    /// Whether the same request is likely to succeed when it's sent again a
+    /// moment later.
+    pub fn is_transient(self) -> bool {
+        matches!(
+            self,
+            Self::ConnectionRefused | Self::Timeout | Self::ConnectionReset | Self::ServerError
+        )
+    }
+}
+
+/// Streams the deltas of a chat completion. Dropping the stream closes the
+/// connection, which makes the server stop generating the rest of the response.
 pub async fn stream_chat_completion(
     client: &dyn HttpClient,
     api_url: &str,
     request: ChatRequest,
     low_speed_timeout: Option<Duration>,
+    http_version: HttpVersion,
 ) -> Result<BoxStream<'static, Result<ChatResponseDelta>>> {
+    stream_chat_completion_with_headers(client, api_url, request, low_speed_timeout, http_version)
+        .await
+        .map(|(_, stream)| stream)
+}
+
+/// Streams a chat completion like [`stream_chat_completion`], also returning the
+/// headers of the response, for diagnosing proxies between Zed and the server.
+pub async fn stream_chat_completion_with_headers(
+    client: &dyn HttpClient,
+    api_url: &str,
+    request: ChatRequest,
+    low_speed_timeout: Option<Duration>,
+    http_version: HttpVersion,
+) -> Result<(
+    Vec<(String, String)>,
+    BoxStream<'static, Result<ChatResponseDelta>>,
+)> {
     let uri = format!("{api_url}/api/chat");
     let mut request_builder = HttpRequest::builder()
         .method(Method::POST)
@@ -177,13 +641,26 @@ pub async fn stream_chat_completion(
     if let Some(low_speed_timeout) = low_speed_timeout {
         request_builder = request_builder.low_speed_timeout(100, low_speed_timeout);
     };
+    if http_version == HttpVersion::Http1 {
+        request_builder = request_builder.version_negotiation(VersionNegotiation::http11());
+    }
 
     let request = request_builder.body(AsyncBody::from(serde_json::to_string(&request)?))?;
     let mut response = client.send(request).await?;
     if response.status().is_success() {
+        let headers = response
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                (
+                    name.to_string(),
+                    String::from_utf8_lossy(value.as_bytes()).into_owned(),
+                )
+            })
+            .collect();
         let reader = BufReader::new(response.into_body());
 
-        Ok(reader
+        let stream = reader
             .lines()
             .filter_map(|line| async move {
                 match line {
@@ -193,16 +670,13 @@ pub async fn stream_chat_completion(
                     Err(e) => Some(Err(e.into())),
                 }
             })
-            .boxed())
+            .boxed();
+        Ok((headers, stream))
     } else {
         let mut body = String::new();
         response.body_mut().read_to_string(&mut body).await?;
 
-        Err(anyhow!(
-            "Failed to connect to Ollama API: {} {}",
-            response.status(),
-            body,
-        ))
+        Err(ApiError::new(response.status(), body).into())
     }
 }
 
@@ -234,11 +708,281 @@ pub async fn get_models(
 
         Ok(response.models)
     } else {
-        Err(anyhow!(
-            "Failed to connect to Ollama API: {} {}",
-            response.status(),
-            body,
-        ))
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct RunningModelsResponse {
+    pub models: Vec<RunningModel>,
+}
+
+/// A model currently loaded into memory on the server.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct RunningModel {
+    pub name: String,
+    /// The memory the loaded model takes up, in bytes.
+    #[serde(default)]
+    pub size: u64,
+    /// How much of `size` is in the GPU's memory, in bytes.
+    #[serde(default)]
+    pub size_vram: u64,
+    /// When the server will unload the model unless it's used again, as an RFC 3339
+    /// timestamp.
+    #[serde(default)]
+    pub expires_at: Option<String>,
+}
+
+/// Lists the models the server currently has loaded into memory.
+pub async fn get_running_models(
+    client: &dyn HttpClient,
+    api_url: &str,
+) -> Result<Vec<RunningModel>> {
+    let uri = format!("{api_url}/api/ps");
+    let request = HttpRequest::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .header("Accept", "application/json")
+        .body(AsyncBody::default())?;
+
+    let mut response = client.send(request).await?;
+
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+
+    if response.status().is_success() {
+        let response: RunningModelsResponse =
+            serde_json::from_str(&body).context("Unable to parse Ollama running models")?;
+
+        Ok(response.models)
+    } else {
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+pub struct ShowModelResponse {
+    /// What the model can do, such as `completion`, `tools`, `insert` or `vision`.
+    /// Only reported by recent versions of Ollama.
+    #[serde(default)]
+    pub capabilities: Vec<String>,
+    /// Metadata of the model's weights, keyed by names such as
+    /// `llama.context_length` that are prefixed with the model's architecture.
+    #[serde(default)]
+    pub model_info: HashMap<String, serde_json::Value>,
+}
+
+impl ShowModelResponse {
+    /// The longest context the model was trained for, in tokens.
+    pub fn context_length(&self) -> Option<usize> {
+        self.model_info
+            .iter()
+            .find(|(key, _)| key.ends_with(".context_length"))
+            .and_then(|(_, value)| value.as_u64())
+            .map(|length| length as usize)
+    }
+}
+
+/// Fetches details about a model, such as its capabilities.
+pub async fn show_model(
+    client: &dyn HttpClient,
+    api_url: &str,
+    model: &str,
+) -> Result<ShowModelResponse> {
+    let uri = format!("{api_url}/api/show");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(
+            &serde_json::json!({ "model": model }),
+        )?))?;
+
+    let mut response = client.send(request).await?;
+
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+
+    if response.status().is_success() {
+        serde_json::from_str(&body).context("Unable to parse Ollama model details")
+    } else {
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+#[derive(Deserialize)]
+struct RenderChatPromptResponse {
+    #[serde(rename = "_debug_info")]
+    debug_info: Option<DebugInfo>,
+}
+
+#[derive(Deserialize)]
+struct DebugInfo {
+    rendered_template: String,
+}
+
+/// Streams the text generated for a prompt without the model's chat template,
+/// such as the code to insert at the cursor. Dropping the stream closes the
+/// connection, which makes the server stop generating.
+pub async fn stream_generate(
+    client: &dyn HttpClient,
+    api_url: &str,
+    request: GenerateRequest,
+    low_speed_timeout: Option<Duration>,
+) -> Result<BoxStream<'static, Result<GenerateResponseDelta>>> {
+    let uri = format!("{api_url}/api/generate");
+    let mut request_builder = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json");
+
+    if let Some(low_speed_timeout) = low_speed_timeout {
+        request_builder = request_builder.low_speed_timeout(100, low_speed_timeout);
+    };
+
+    let request = request_builder.body(AsyncBody::from(serde_json::to_string(&request)?))?;
+    let mut response = client.send(request).await?;
+    if response.status().is_success() {
+        let reader = BufReader::new(response.into_body());
+        Ok(reader
+            .lines()
+            .map(|line| serde_json::from_str(&line?).context("Unable to parse generate response"))
+            .boxed())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+/// Asks the server to render `request` with the model's chat template without
+/// running the model, returning the prompt exactly as the model would see it.
+/// Servers that don't support this run the model instead, so generation is
+/// limited to a single token.
+pub async fn render_chat_prompt(
+    client: &dyn HttpClient,
+    api_url: &str,
+    mut request: ChatRequest,
+) -> Result<String> {
+    request.stream = false;
+    request.debug_render_only = true;
+    request
+        .options
+        .get_or_insert_with(Default::default)
+        .num_predict = Some(1);
+
+    let uri = format!("{api_url}/api/chat");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(&request)?))?;
+
+    let mut response = client.send(request).await?;
+
+    let mut body = String::new();
+    response.body_mut().read_to_string(&mut body).await?;
+
+    if response.status().is_success() {
+        let response: RenderChatPromptResponse =
+            serde_json::from_str(&body).context("Unable to parse Ollama rendered prompt")?;
+        response
+            .debug_info
+            .map(|debug_info| debug_info.rendered_template)
+            .context("This version of Ollama can't render prompts")
+    } else {
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct PullProgress {
+    pub status: String,
+    pub digest: Option<String>,
+    pub total: Option<u64>,
+    pub completed: Option<u64>,
+}
+
+impl PullProgress {
+    /// How much of the layer being downloaded has arrived, from 0 to 1, when the
+    /// server reports its size.
+    pub fn fraction_completed(&self) -> Option<f64> {
+        match (self.completed, self.total) {
+            (Some(completed), Some(total)) if total > 0 => Some(completed as f64 / total as f64),
+            _ => None,
+        }
+    }
+}
+
+pub async fn pull_model(
+    client: &dyn HttpClient,
+    api_url: &str,
+    model: &str,
+) -> Result<BoxStream<'static, Result<PullProgress>>> {
+    let uri = format!("{api_url}/api/pull");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(
+            &serde_json::json!({
+                "name": model,
+                "stream": true,
+            }),
+        )?))?;
+
+    let mut response = client.send(request).await?;
+    if response.status().is_success() {
+        let reader = BufReader::new(response.into_body());
+
+        Ok(reader
+            .lines()
+            .filter_map(|line| async move {
+                match line {
+                    Ok(line) => {
+                        Some(serde_json::from_str(&line).context("Unable to parse pull progress"))
+                    }
+                    Err(e) => Some(Err(e.into())),
+                }
+            })
+            .boxed())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+/// Sends an empty embedding request to Ollama to trigger loading an embedding model
+pub async fn preload_embedding_model(
+    client: &dyn HttpClient,
+    api_url: &str,
+    model: &str,
+) -> Result<()> {
+    let uri = format!("{api_url}/api/embed");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(
+            &serde_json::json!({
+                "model": model,
+                "input": "",
+                "keep_alive": "15m",
+            }),
+        )?))?;
+
+    let mut response = client.send(request).await?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        Err(ApiError::new(response.status(), body).into())
     }
 }
 
@@ -274,10 +1018,94 @@ pub async fn preload_model(client: &dyn HttpClient, api_url: &str, model: &str)
         let mut body = String::new();
         response.body_mut().read_to_string(&mut body).await?;
 
-        Err(anyhow!(
-            "Failed to connect to Ollama API: {} {}",
-            response.status(),
-            body,
-        ))
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+/// Sets how long Ollama keeps the model loaded after its last request, loading it
+/// if it isn't loaded already. [`KeepAlive::indefinite`] keeps it loaded until
+/// it's unloaded explicitly or the server stops.
+pub async fn set_keep_alive(
+    client: &dyn HttpClient,
+    api_url: &str,
+    model: &str,
+    keep_alive: KeepAlive,
+) -> Result<()> {
+    let uri = format!("{api_url}/api/generate");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(
+            &serde_json::json!({
+                "model": model,
+                "keep_alive": keep_alive,
+            }),
+        )?))?;
+
+    let mut response = client.send(request).await?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+/// Asks Ollama to unload the model from memory right away, instead of when its
+/// keep-alive duration expires.
+pub async fn unload_model(client: &dyn HttpClient, api_url: &str, model: &str) -> Result<()> {
+    let uri = format!("{api_url}/api/generate");
+    let request = HttpRequest::builder()
+        .method(Method::POST)
+        .uri(uri)
+        .header("Content-Type", "application/json")
+        .body(AsyncBody::from(serde_json::to_string(
+            &serde_json::json!({
+                "model": model,
+                "keep_alive": 0,
+            }),
+        )?))?;
+
+    let mut response = client.send(request).await?;
+
+    if response.status().is_success() {
+        Ok(())
+    } else {
+        let mut body = String::new();
+        response.body_mut().read_to_string(&mut body).await?;
+
+        Err(ApiError::new(response.status(), body).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pull_progress_fraction_completed() {
+        let progress = |total, completed| PullProgress {
+            status: "pulling 6a0746a1ec1a".into(),
+            digest: Some("sha256:6a0746a1ec1a".into()),
+            total,
+            completed,
+        };
+
+        assert_eq!(
+            progress(Some(400), Some(100)).fraction_completed(),
+            Some(0.25)
+        );
+        assert_eq!(
+            progress(Some(400), Some(400)).fraction_completed(),
+            Some(1.0)
+        );
+        // Without a known, non-empty layer size there's nothing to measure against.
+        assert_eq!(progress(Some(0), Some(0)).fraction_completed(), None);
+        assert_eq!(progress(None, Some(100)).fraction_completed(), None);
+        assert_eq!(progress(Some(400), None).fraction_completed(), None);
     }
 }
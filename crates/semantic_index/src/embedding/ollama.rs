@@ -1,4 +1,4 @@
-use anyhow::{Context as _, Result};
+use anyhow::{anyhow, Context as _, Result};
 use futures::{future::BoxFuture, AsyncReadExt, FutureExt};
 use http::HttpClient;
 use serde::{Deserialize, Serialize};
@@ -9,11 +9,32 @@ use crate::{Embedding, EmbeddingProvider, TextToEmbed};
 pub enum OllamaEmbeddingModel {
     NomicEmbedText,
     MxbaiEmbedLarge,
+    /// Any other embedding model installed on the server, by name.
+    Custom(String),
+}
+
+impl OllamaEmbeddingModel {
+    pub fn from_id(id: &str) -> Self {
+        match id {
+            "nomic-embed-text" | "nomic-embed-text:latest" => Self::NomicEmbedText,
+            "mxbai-embed-large" | "mxbai-embed-large:latest" => Self::MxbaiEmbedLarge,
+            _ => Self::Custom(id.to_string()),
+        }
+    }
+
+    pub fn id(&self) -> &str {
+        match self {
+            Self::NomicEmbedText => "nomic-embed-text",
+            Self::MxbaiEmbedLarge => "mxbai-embed-large",
+            Self::Custom(id) => id,
+        }
+    }
 }
 
 pub struct OllamaEmbeddingProvider {
     client: Arc<dyn HttpClient>,
     model: OllamaEmbeddingModel,
+    api_url: String,
 }
 
 #[derive(Serialize)]
@@ -28,32 +49,35 @@ struct OllamaEmbeddingResponse {
 }
 
 impl OllamaEmbeddingProvider {
-    pub fn new(client: Arc<dyn HttpClient>, model: OllamaEmbeddingModel) -> Self {
-        Self { client, model }
+    pub fn new(client: Arc<dyn HttpClient>, model: OllamaEmbeddingModel, api_url: String) -> Self {
+        Self {
+            client,
+            model,
+            api_url,
+        }
     }
 }
 
 impl EmbeddingProvider for OllamaEmbeddingProvider {
     fn embed<'a>(&'a self, texts: &'a [TextToEmbed<'a>]) -> BoxFuture<'a, Result<Vec<Embedding>>> {
-        //
-        let model = match self.model {
-            OllamaEmbeddingModel::NomicEmbedText => "nomic-embed-text",
-            OllamaEmbeddingModel::MxbaiEmbedLarge => "mxbai-embed-large",
-        };
-
         futures::future::try_join_all(texts.into_iter().map(|to_embed| {
             let request = OllamaEmbeddingRequest {
-                model: model.to_string(),
+                model: self.model.id().to_string(),
                 prompt: to_embed.text.to_string(),
             };
 
             let request = serde_json::to_string(&request).unwrap();
 
-            async {
-                let response = self
-                    .client
-                    .post_json("http://localhost:11434/api/embeddings", request.into())
-                    .await?;
+            let uri = format!("{}/api/embeddings", self.api_url);
+            async move {
+                let response = self.client.post_json(&uri, request.into()).await?;
+                if !response.status().is_success() {
+                    return Err(anyhow!(
+                        "Ollama failed to embed text with {}: {}",
+                        self.model.id(),
+                        response.status()
+                    ));
+                }
 
                 let mut body = String::new();
                 response.into_body().read_to_string(&mut body).await?;
@@ -72,3 +96,79 @@ impl EmbeddingProvider for OllamaEmbeddingProvider {
         10
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use http::FakeHttpClient;
+
+    #[test]
+    fn test_model_from_id() {
+        assert!(matches!(
+            OllamaEmbeddingModel::from_id("nomic-embed-text:latest"),
+            OllamaEmbeddingModel::NomicEmbedText
+        ));
+        assert!(matches!(
+            OllamaEmbeddingModel::from_id("mxbai-embed-large"),
+            OllamaEmbeddingModel::MxbaiEmbedLarge
+        ));
+
+        let model = OllamaEmbeddingModel::from_id("all-minilm:33m");
+        assert!(matches!(model, OllamaEmbeddingModel::Custom(_)));
+        assert_eq!(model.id(), "all-minilm:33m");
+    }
+
+    #[gpui::test]
+    async fn test_embed() {
+        let client = FakeHttpClient::create(|request| async move {
+            assert_eq!(
+                request.uri().to_string(),
+                "http://inference.example.com:11434/api/embeddings"
+            );
+            let mut body = String::new();
+            request.into_body().read_to_string(&mut body).await.unwrap();
+            let body: serde_json::Value = serde_json::from_str(&body).unwrap();
+            assert_eq!(body["model"], "nomic-embed-text");
+            assert_eq!(body["prompt"], "fn main() {}");
+            Ok(http::Response::builder()
+                .status(200)
+                .body(r#"{"embedding":[3.0,4.0]}"#.into())
+                .unwrap())
+        });
+        let provider = OllamaEmbeddingProvider::new(
+            client,
+            OllamaEmbeddingModel::NomicEmbedText,
+            "http://inference.example.com:11434".into(),
+        );
+
+        let embeddings = provider
+            .embed(&[TextToEmbed::new("fn main() {}")])
+            .await
+            .unwrap();
+        assert_eq!(embeddings, [Embedding::new(vec![3.0, 4.0])]);
+    }
+
+    #[gpui::test]
+    async fn test_embed_error_status() {
+        let client = FakeHttpClient::create(|_| async move {
+            Ok(http::Response::builder()
+                .status(404)
+                .body(r#"{"error":"model \"all-minilm\" not found"}"#.into())
+                .unwrap())
+        });
+        let provider = OllamaEmbeddingProvider::new(
+            client,
+            OllamaEmbeddingModel::from_id("all-minilm"),
+            "http://localhost:11434".into(),
+        );
+
+        let error = provider
+            .embed(&[TextToEmbed::new("fn main() {}")])
+            .await
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Ollama failed to embed text with all-minilm: 404 Not Found"
+        );
+    }
+}
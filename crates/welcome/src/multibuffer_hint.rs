@@ -1,14 +1,22 @@
-use std::collections::HashSet;
-use std::sync::atomic::{AtomicUsize, Ordering};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
 use std::sync::OnceLock;
 
+use anyhow::Result;
 use db::kvp::KEY_VALUE_STORE;
-use gpui::{AppContext, Empty, EntityId, EventEmitter};
+use gpui::{AppContext, Empty, EntityId, EventEmitter, Model};
+use parking_lot::Mutex;
+use project::Project;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use settings::{Settings, SettingsSources};
 use ui::{prelude::*, ButtonLike, IconButtonShape, Tooltip};
+use util::ResultExt;
 use workspace::item::ItemHandle;
 use workspace::{ToolbarItemEvent, ToolbarItemLocation, ToolbarItemView};
 
 pub struct MultibufferHint {
+    project: Model<Project>,
     shown_on: HashSet<EntityId>,
     active_item: Option<Box<dyn ItemHandle>>,
 }
@@ -17,48 +25,135 @@ const NUMBER_OF_HINTS: usize = 10;
 
 const SHOWN_COUNT_KEY: &str = "MULTIBUFFER_HINT_SHOWN_COUNT";
 
+const PROJECT_SHOWN_COUNTS_KEY: &str = "MULTIBUFFER_HINT_PROJECT_SHOWN_COUNTS";
+
+#[derive(Deserialize, Debug)]
+pub struct MultibufferHintSettings {
+    pub per_project: bool,
+}
+
+/// Multibuffer hint configuration.
+#[derive(Clone, Default, Serialize, Deserialize, JsonSchema, Debug)]
+pub struct MultibufferHintSettingsContent {
+    /// Whether the number of times the hint was shown is counted separately
+    /// for each project root, so that it re-appears in new projects.
+    ///
+    /// Default: false
+    per_project: Option<bool>,
+}
+
+impl Settings for MultibufferHintSettings {
+    const KEY: Option<&'static str> = Some("multibuffer_hint");
+    type FileContent = MultibufferHintSettingsContent;
+
+    fn load(sources: SettingsSources<Self::FileContent>, _: &mut AppContext) -> Result<Self> {
+        sources.json_merge()
+    }
+}
+
 impl MultibufferHint {
-    pub fn new() -> Self {
+    pub fn new(project: Model<Project>) -> Self {
         Self {
+            project,
             shown_on: Default::default(),
             active_item: None,
         }
     }
 }
 
+/// How many times the hint was shown, overall and for each project root whose
+/// views are counted separately.
+struct ShownCounts {
+    global: usize,
+    per_project: HashMap<String, usize>,
+}
+
 impl MultibufferHint {
-    fn counter() -> &'static AtomicUsize {
-        static SHOWN_COUNT: OnceLock<AtomicUsize> = OnceLock::new();
-        SHOWN_COUNT.get_or_init(|| {
-            let value: usize = KEY_VALUE_STORE
+    fn counts() -> &'static Mutex<ShownCounts> {
+        static SHOWN_COUNTS: OnceLock<Mutex<ShownCounts>> = OnceLock::new();
+        SHOWN_COUNTS.get_or_init(|| {
+            let global = KEY_VALUE_STORE
                 .read_kvp(SHOWN_COUNT_KEY)
                 .ok()
                 .flatten()
                 .and_then(|v| v.parse().ok())
                 .unwrap_or(0);
-
-            AtomicUsize::new(value)
+            let per_project = KEY_VALUE_STORE
+                .read_kvp(PROJECT_SHOWN_COUNTS_KEY)
+                .ok()
+                .flatten()
+                .and_then(|v| serde_json::from_str(&v).ok())
+                .unwrap_or_default();
+            Mutex::new(ShownCounts {
+                global,
+                per_project,
+            })
         })
     }
 
-    fn shown_count() -> usize {
-        Self::counter().load(Ordering::Relaxed)
+    /// The project root whose views are counted separately, or `None` if they
+    /// count towards the global counter.
+    fn counted_root(root: Option<&Path>, cx: &AppContext) -> Option<String> {
+        root.filter(|_| MultibufferHintSettings::get_global(cx).per_project)
+            .map(|root| root.display().to_string())
     }
 
-    fn increment_count(cx: &mut AppContext) {
-        Self::set_count(Self::shown_count() + 1, cx)
+    fn count_root(&self, cx: &AppContext) -> Option<String> {
+        let root = self
+            .project
+            .read(cx)
+            .visible_worktrees(cx)
+            .next()
+            .map(|worktree| worktree.read(cx).abs_path());
+        Self::counted_root(root.as_deref(), cx)
     }
 
-    pub(crate) fn set_count(count: usize, cx: &mut AppContext) {
-        Self::counter().store(count, Ordering::Relaxed);
+    fn shown_count(root: Option<&str>) -> usize {
+        let counts = Self::counts().lock();
+        match root {
+            Some(root) => counts.per_project.get(root).copied().unwrap_or(0),
+            None => counts.global,
+        }
+    }
+
+    fn increment_count(root: Option<&str>, cx: &mut AppContext) {
+        Self::set_count_for_root(root, Self::shown_count(root) + 1, cx)
+    }
 
-        db::write_and_log(cx, move || {
-            KEY_VALUE_STORE.write_kvp(SHOWN_COUNT_KEY.to_string(), format!("{}", count))
+    fn set_count_for_root(root: Option<&str>, count: usize, cx: &mut AppContext) {
+        let mut counts = Self::counts().lock();
+        match root {
+            Some(root) => {
+                counts.per_project.insert(root.to_string(), count);
+                let Some(per_project) = serde_json::to_string(&counts.per_project).log_err() else {
+                    return;
+                };
+                db::write_and_log(cx, move || {
+                    KEY_VALUE_STORE.write_kvp(PROJECT_SHOWN_COUNTS_KEY.to_string(), per_project)
+                });
+            }
+            None => {
+                counts.global = count;
+                db::write_and_log(cx, move || {
+                    KEY_VALUE_STORE.write_kvp(SHOWN_COUNT_KEY.to_string(), format!("{}", count))
+                });
+            }
+        }
+    }
+
+    /// Sets the global shown count and forgets the per-project counts, so that
+    /// the hint is shown again in every project.
+    pub(crate) fn set_count(count: usize, cx: &mut AppContext) {
+        Self::set_count_for_root(None, count, cx);
+        Self::counts().lock().per_project.clear();
+        db::write_and_log(cx, || {
+            KEY_VALUE_STORE.delete_kvp(PROJECT_SHOWN_COUNTS_KEY.to_string())
         });
     }
 
     fn dismiss(&mut self, cx: &mut AppContext) {
-        Self::set_count(NUMBER_OF_HINTS, cx)
+        let root = self.count_root(cx);
+        Self::set_count_for_root(root.as_deref(), NUMBER_OF_HINTS, cx)
     }
 }
 
@@ -70,7 +165,8 @@ impl ToolbarItemView for MultibufferHint {
         active_pane_item: Option<&dyn ItemHandle>,
         cx: &mut ViewContext<Self>,
     ) -> ToolbarItemLocation {
-        if Self::shown_count() > NUMBER_OF_HINTS {
+        let count_root = self.count_root(cx);
+        if Self::shown_count(count_root.as_deref()) > NUMBER_OF_HINTS {
             return ToolbarItemLocation::Hidden;
         }
 
@@ -83,7 +179,7 @@ impl ToolbarItemView for MultibufferHint {
         }
 
         if self.shown_on.insert(active_pane_item.item_id()) {
-            Self::increment_count(cx)
+            Self::increment_count(count_root.as_deref(), cx)
         }
 
         self.active_item = Some(active_pane_item.boxed_clone());
@@ -140,3 +236,70 @@ impl Render for MultibufferHint {
             .into_any_element()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gpui::TestAppContext;
+    use settings::SettingsStore;
+
+    #[gpui::test]
+    async fn test_per_project_counts(cx: &mut TestAppContext) {
+        cx.update(|cx| {
+            let store = SettingsStore::test(cx);
+            cx.set_global(store);
+            MultibufferHintSettings::register(cx);
+        });
+
+        let first_root = Path::new("/first-project");
+        let second_root = Path::new("/second-project");
+
+        cx.update(|cx| {
+            assert_eq!(MultibufferHint::counted_root(Some(first_root), cx), None);
+            assert_eq!(MultibufferHint::counted_root(Some(second_root), cx), None);
+        });
+
+        cx.update(|cx| {
+            cx.update_global::<SettingsStore, _>(|store, cx| {
+                store.update_user_settings::<MultibufferHintSettings>(cx, |settings| {
+                    settings.per_project = Some(true);
+                });
+            });
+        });
+
+        let (first, second) = cx.update(|cx| {
+            let first = MultibufferHint::counted_root(Some(first_root), cx);
+            let second = MultibufferHint::counted_root(Some(second_root), cx);
+            assert_ne!(first, second);
+
+            for _ in 0..3 {
+                MultibufferHint::increment_count(first.as_deref(), cx);
+            }
+            MultibufferHint::increment_count(second.as_deref(), cx);
+
+            assert_eq!(MultibufferHint::shown_count(first.as_deref()), 3);
+            assert_eq!(MultibufferHint::shown_count(second.as_deref()), 1);
+            (first, second)
+        });
+        cx.run_until_parked();
+        let persisted_counts: HashMap<String, usize> = serde_json::from_str(
+            &KEY_VALUE_STORE
+                .read_kvp(PROJECT_SHOWN_COUNTS_KEY)
+                .unwrap()
+                .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(persisted_counts.len(), 2);
+
+        // Resetting the hints forgets the counts of every project, both in memory
+        // and in the database.
+        cx.update(|cx| MultibufferHint::set_count(0, cx));
+        cx.run_until_parked();
+        assert_eq!(MultibufferHint::shown_count(first.as_deref()), 0);
+        assert_eq!(MultibufferHint::shown_count(second.as_deref()), 0);
+        assert_eq!(
+            KEY_VALUE_STORE.read_kvp(PROJECT_SHOWN_COUNTS_KEY).unwrap(),
+            None
+        );
+    }
+}
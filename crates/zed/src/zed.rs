@@ -493,9 +493,10 @@ pub fn initialize_workspace(app_state: Arc<AppState>, cx: &mut AppContext) {
 }
 
 fn initialize_pane(workspace: &mut Workspace, pane: &View<Pane>, cx: &mut ViewContext<Workspace>) {
+    let project = workspace.project().clone();
     pane.update(cx, |pane, cx| {
         pane.toolbar().update(cx, |toolbar, cx| {
-            let multibuffer_hint = cx.new_view(|_| MultibufferHint::new());
+            let multibuffer_hint = cx.new_view(|_| MultibufferHint::new(project));
             toolbar.add_item(multibuffer_hint, cx);
             let breadcrumbs = cx.new_view(|_| Breadcrumbs::new());
             toolbar.add_item(breadcrumbs, cx);